@@ -172,6 +172,7 @@ impl Interpreter {
                     span,
                 );
             }
+            Expr::Group(inner) => self.interpret_expr(proc, inner),
             Expr::Ident(ident) => {
                 let var = self.lookup.get(ident.clone());
 
@@ -440,11 +440,11 @@ impl Program {
                 ]
                 .concat()
             });
-            bytes.extend_from_slice(&*span_bytes.unwrap_or_else(|| vec![0_u8]));
+            bytes.extend_from_slice(&span_bytes.unwrap_or_else(|| vec![0_u8]));
 
             let name_bytes = name.clone().map(|s| s.as_bytes().to_vec());
             bytes.extend_from_slice(&name_bytes.clone().map_or(0, |n| n.len()).to_ne_bytes());
-            bytes.extend_from_slice(&*name_bytes.unwrap_or_default());
+            bytes.extend_from_slice(&name_bytes.unwrap_or_default());
         }
 
         bytes
@@ -0,0 +1,38 @@
+#![feature(test)]
+
+extern crate test;
+
+use terbium_grammar::token::lex_parallel_with_chunk_size;
+use terbium_grammar::{ParseInterface, Source, Span, Token};
+use test::{black_box, Bencher};
+
+const SNIPPET: &str = r#"func add(a, b) {
+    // Adds two numbers
+    return a + b;
+}
+
+let message = "hello
+world";
+/* computes something */
+print(add(1, 2) * 3 ** 4);
+"#;
+
+fn source() -> String {
+    SNIPPET.repeat(200)
+}
+
+#[bench]
+fn bench_lex_serial(b: &mut Bencher) {
+    let src = source();
+
+    b.iter(|| {
+        black_box(Vec::<(Token, Span)>::from_string(Source::default(), src.clone()).unwrap())
+    });
+}
+
+#[bench]
+fn bench_lex_parallel(b: &mut Bencher) {
+    let src = source();
+
+    b.iter(|| black_box(lex_parallel_with_chunk_size(&src, 2048).unwrap()));
+}
@@ -0,0 +1,123 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use terbium_grammar::token::Lexer;
+use terbium_grammar::{ChumskyParser, ChumskyStream, Source, Span};
+use test::{black_box, Bencher};
+
+/// Counts allocations so that the benches can report how many each approach makes.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const SNIPPETS: [&str; 4] = [
+    "let x = 1;",
+    "print(x + y * 2);",
+    "func add(a, b) { a + b }",
+    "if x >= 10 { x = 0; }",
+];
+
+fn report(name: &str, mut f: impl FnMut()) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+
+    eprintln!("{}: {} allocations per iteration", name, after - before);
+}
+
+#[bench]
+fn bench_get_lexer(b: &mut Bencher) {
+    let mut run = || {
+        for snippet in SNIPPETS {
+            black_box(
+                terbium_grammar::tokenizer()
+                    .parse(ChumskyStream::<_, Span, _>::from_iter(
+                        Span::single(Source::default(), snippet.chars().count()),
+                        snippet
+                            .chars()
+                            .enumerate()
+                            .map(|(i, c)| (c, Span::single(Source::default(), i))),
+                    ))
+                    .unwrap(),
+            );
+        }
+    };
+
+    report("get_lexer", &mut run);
+    b.iter(run);
+}
+
+#[bench]
+fn bench_lex_into(b: &mut Bencher) {
+    let mut lexer = Lexer::new();
+    let mut out = Vec::new();
+    let mut run = || {
+        for snippet in SNIPPETS {
+            lexer.lex_into(snippet, &mut out).unwrap();
+            black_box(&out);
+        }
+    };
+
+    // Warm up the reused buffers first
+    run();
+    report("lex_into", &mut run);
+    b.iter(run);
+}
+
+/// A large source file, with every string and comment in `text`.
+fn file(text: &str) -> String {
+    format!(
+        r#"func greet(name) {{
+    // {text}
+    print("{text}, " + name);
+}}
+/* {text} */
+let total = greet("x") * 2 ** 3;
+"#
+    )
+    .repeat(100)
+}
+
+#[bench]
+fn bench_lex_ascii_file(b: &mut Bencher) {
+    let src = file("Hello there");
+    let mut lexer = Lexer::new();
+    let mut out = Vec::new();
+
+    b.iter(|| {
+        lexer.lex_into(&src, &mut out).unwrap();
+        black_box(&out);
+    });
+}
+
+/// The same file as [`bench_lex_ascii_file`] but with non-ASCII strings and comments, to
+/// compare how much handling Unicode costs.
+#[bench]
+fn bench_lex_unicode_file(b: &mut Bencher) {
+    let src = file("Grüß dich 👋");
+    let mut lexer = Lexer::new();
+    let mut out = Vec::new();
+
+    b.iter(|| {
+        lexer.lex_into(&src, &mut out).unwrap();
+        black_box(&out);
+    });
+}
@@ -1,8 +1,15 @@
-use super::token::{get_lexer, Bracket, Keyword, Literal, Operator, StringLiteral, Token};
+use super::cursor::Cursor;
+use super::token::{
+    get_lexer, Bracket, Keyword, Literal, Operator, StringLiteral, Token, TokenStream,
+};
 use super::{Error, Source, Span, Spanned};
 
+#[cfg(feature = "std")]
 use std::path::Path;
 
+#[allow(deprecated)]
+use chumsky::debug::{Debugger, Silent, Verbose};
+use chumsky::error::Located;
 use chumsky::prelude::*;
 use chumsky::Stream;
 
@@ -32,6 +39,13 @@ pub enum Expr {
         rhs: SpannedExpr,
     },
     Attr(SpannedExpr, String),
+    /// An expression within parentheses. Its span includes the parentheses.
+    Group(SpannedExpr),
+    /// `start..end`, where either bound may be left out, e.g. `..5` or `5..`.
+    Range {
+        start: Option<SpannedExpr>,
+        end: Option<SpannedExpr>,
+    },
     Call {
         value: SpannedExpr,
         args: Vec<SpannedExpr>,
@@ -106,6 +120,62 @@ pub enum TypeExpr {
     Any,
 }
 
+/// The nesting depth [`ParseInterface::from_string`] allows before giving up.
+///
+/// Parsing grows the stack as needed, so this only bounds how deep the resulting tree is,
+/// which code walking it recursively, such as its destructor, has to cope with.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// Ensures the given tokens don't nest deeper than `max_depth`.
+///
+/// Every bracket opens a nesting level, and so does every operator, since each one nests the
+/// expressions around it by one more level, e.g. `a + b + c` and `- - a` nest two levels deep.
+/// Operators are counted per bracket, until the next `,` or `;`, or the end of a block, which
+/// ends the statement it belongs to, as in `if a == b { c; }`.
+///
+/// # Errors
+/// * A bracket or operator opens a nesting level deeper than `max_depth`.
+#[allow(clippy::result_large_err)]
+pub fn check_depth(tokens: &[(Token, Span)], max_depth: usize) -> Result<(), Error> {
+    let mut depth = 0_usize;
+    // The number of operators since the start of the expression, per bracket
+    let mut operators = vec![0_usize];
+
+    for (token, span) in tokens {
+        match token {
+            Token::StartBracket(_) => {
+                depth += 1;
+                operators.push(0);
+            }
+            // An unmatched closing bracket is left for the parser to report
+            Token::EndBracket(bracket) => {
+                if operators.len() > 1 {
+                    depth -= operators.pop().unwrap_or_default() + 1;
+                }
+                if *bracket == Bracket::Brace {
+                    depth -= core::mem::take(operators.last_mut().unwrap());
+                }
+                continue;
+            }
+            Token::Operator(_) => {
+                depth += 1;
+                *operators.last_mut().unwrap() += 1;
+            }
+            Token::Comma | Token::Semicolon => {
+                depth -= core::mem::take(operators.last_mut().unwrap());
+                continue;
+            }
+            _ => continue,
+        }
+
+        if depth > max_depth {
+            return Err(Error::custom(span.clone(), "expression nesting too deep"));
+        }
+    }
+
+    Ok(())
+}
+
 pub trait ParseInterface {
     // Parses the given tokens using this parser.
     ///
@@ -115,6 +185,24 @@ pub trait ParseInterface {
     where
         Self: Sized;
 
+    /// Parses the given tokens using this parser, refusing to build a tree nested deeper than
+    /// `max_depth`, see [`check_depth`].
+    ///
+    /// # Errors
+    /// * The tokens are nested deeper than `max_depth`.
+    /// * The tokens does not match Terbium grammar.
+    fn parse_with_max_depth(
+        tokens: Vec<(Token, Span)>,
+        max_depth: usize,
+    ) -> Result<Self, Vec<Error>>
+    where
+        Self: Sized,
+    {
+        check_depth(&tokens, max_depth).map_err(|e| vec![e])?;
+
+        Self::parse(tokens)
+    }
+
     /// Tokenizes source code and parses it using this parser.
     ///
     /// # Errors
@@ -130,13 +218,14 @@ pub trait ParseInterface {
                 .map(|(i, c)| (c, Span::single(source.clone(), i))),
         ))?;
 
-        Self::parse(tokens)
+        Self::parse_with_max_depth(tokens, DEFAULT_MAX_DEPTH)
     }
 
     /// Reads and tokenizes the specified source file.
     ///
     /// # Errors
     /// * The source file's content does not match Terbium grammar.
+    #[cfg(feature = "std")]
     fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Vec<Error>>
     where
         Self: Sized,
@@ -158,8 +247,8 @@ pub enum Target {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Param {
     // TODO: typing
-    target: SpannedTarget,
-    default: Option<SpannedExpr>,
+    pub(crate) target: SpannedTarget,
+    pub(crate) default: Option<SpannedExpr>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -190,6 +279,59 @@ pub enum Node {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Body(pub Vec<SpannedNode>, pub bool); // body, return_last
 
+/// A generic parameter and the types it is bound to, e.g. the `T: A + B` in `where T: A + B`.
+///
+/// A bare `T` has no bounds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bound {
+    pub param: String,
+    pub bounds: Vec<TypeExpr>,
+}
+
+/// A module path along with the name it is bound to, e.g. `a.b as c`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportItem {
+    /// The names that make up the path, e.g. `["a", "b"]` for `a.b`.
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+}
+
+/// The shape of an import statement, i.e. `require a.b as c, d;` or
+/// `from a.b require c, d as e;`. See [`parse_import`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportStmt {
+    /// The module which the items are imported from, if the statement starts with `from`.
+    pub from: Option<Vec<String>>,
+    pub items: Vec<ImportItem>,
+}
+
+/// The modifiers of a binding, e.g. `private` and `mut` in `private let mut x = 1;`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BindingModifiers {
+    pub private: bool,
+    /// Whether the binding is declared with `const` rather than `let`.
+    pub r#const: bool,
+    pub r#mut: bool,
+}
+
+/// A single binding statement such as `private let mut x = 1;`. See [`parse_let`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct LetBinding {
+    pub modifiers: BindingModifiers,
+    /// The targets which are assigned the value, e.g. both `a` and `b` in `let a = b = 1;`.
+    pub targets: Vec<SpannedTarget>,
+    pub value: SpannedExpr,
+}
+
+/// A decorator such as `@cached` or `@route("/x")`, which annotates the declaration that
+/// follows it. See [`parse_decorator`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decorator {
+    pub name: String,
+    /// The arguments within parentheses, or `None` if the decorator has no parentheses.
+    pub args: Option<Vec<SpannedExpr>>,
+}
+
 impl ParseInterface for Vec<(Token, Span)> {
     fn parse(tokens: Vec<(Token, Span)>) -> Result<Self, Vec<Error>>
     where
@@ -199,6 +341,15 @@ impl ParseInterface for Vec<(Token, Span)> {
     }
 }
 
+impl ParseInterface for TokenStream {
+    fn parse(tokens: Vec<(Token, Span)>) -> Result<Self, Vec<Error>>
+    where
+        Self: Sized,
+    {
+        Ok(Self(tokens))
+    }
+}
+
 impl ParseInterface for Expr {
     fn parse(mut tokens: Vec<(Token, Span)>) -> Result<Self, Vec<Error>>
     where
@@ -216,7 +367,7 @@ impl ParseInterface for Expr {
             .map(|b| {
                 let Body(body, _) = b.into_node();
 
-                if let Node::Expr(e) = body.get(0).unwrap().node() {
+                if let Node::Expr(e) = body.first().unwrap().node() {
                     e.clone().into_node()
                 } else {
                     unreachable!();
@@ -263,9 +414,189 @@ impl ParseInterface for Body {
     }
 }
 
+impl ParseInterface for ImportStmt {
+    fn parse(tokens: Vec<(Token, Span)>) -> Result<Self, Vec<Error>>
+    where
+        Self: Sized,
+    {
+        let eoi = tokens.last().map_or_else(Span::default, |(_, span)| {
+            Span::single(span.src(), span.end())
+        });
+
+        get_import_parser()
+            .then_ignore(end())
+            .parse(Stream::<_, Span, _>::from_iter(eoi, tokens.into_iter()))
+    }
+}
+
+impl ParseInterface for Bound {
+    fn parse(tokens: Vec<(Token, Span)>) -> Result<Self, Vec<Error>>
+    where
+        Self: Sized,
+    {
+        let eoi = tokens.last().map_or_else(Span::default, |(_, span)| {
+            Span::single(span.src(), span.end())
+        });
+
+        get_bound_parser()
+            .then_ignore(end())
+            .parse(Stream::<_, Span, _>::from_iter(eoi, tokens.into_iter()))
+    }
+}
+
+impl ParseInterface for LetBinding {
+    fn parse(tokens: Vec<(Token, Span)>) -> Result<Self, Vec<Error>>
+    where
+        Self: Sized,
+    {
+        check_modifier_order(&tokens).map_err(|e| vec![e])?;
+
+        let mut cursor = Cursor::new(&tokens);
+        let private = cursor.eat(&Token::Keyword(Keyword::Private)).is_some();
+
+        let expected = [
+            Some(Token::Keyword(Keyword::Let)),
+            Some(Token::Keyword(Keyword::Const)),
+        ];
+        match cursor.peek() {
+            Some((Token::Keyword(Keyword::Let | Keyword::Const), _)) => (),
+            Some((found, span)) => {
+                return Err(vec![chumsky::Error::expected_input_found(
+                    span.clone(),
+                    expected,
+                    Some(found.clone()),
+                )])
+            }
+            None => {
+                return Err(vec![chumsky::Error::expected_input_found(
+                    cursor.end_span(),
+                    expected,
+                    None,
+                )])
+            }
+        }
+
+        // The rest is an ordinary declaration, whose semicolon is optional here
+        let mut declaration = cursor.remaining().to_vec();
+        let (last, span) = declaration.last().cloned().unwrap();
+        if last != Token::Semicolon {
+            declaration.push((Token::Semicolon, Span::single(span.src(), span.end())));
+        }
+        let eoi = Span::single(span.src(), span.end() + 1);
+
+        let Body(mut nodes, _) = get_body_parser()
+            .then_ignore(end())
+            .parse(Stream::<_, Span, _>::from_iter(
+                eoi,
+                declaration.into_iter(),
+            ))?
+            .into_node();
+
+        if nodes.len() > 1 {
+            return Err(vec![Error::custom(
+                nodes[1].span(),
+                "expected a single binding",
+            )]);
+        }
+        match nodes.remove(0).into_node() {
+            Node::Declare {
+                targets,
+                value,
+                r#mut,
+                r#const,
+            } => Ok(Self {
+                modifiers: BindingModifiers {
+                    private,
+                    r#const,
+                    r#mut,
+                },
+                targets,
+                value,
+            }),
+            _ => unreachable!("a statement starting with 'let' or 'const' is a declaration"),
+        }
+    }
+}
+
+/// Ensures the binding modifiers at the start of `tokens` come in the order `private`, then
+/// `let` or `const`, then `mut`.
+#[allow(clippy::result_large_err)]
+fn check_modifier_order(tokens: &[(Token, Span)]) -> Result<(), Error> {
+    let rank = |keyword: &Keyword| match keyword {
+        Keyword::Private => Some(0),
+        Keyword::Let | Keyword::Const => Some(1),
+        Keyword::Mut => Some(2),
+        _ => None,
+    };
+    let modifiers = tokens
+        .iter()
+        .map_while(|(token, span)| match token {
+            Token::Keyword(keyword) => Some((keyword, rank(keyword)?, span)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for pair in modifiers.windows(2) {
+        let [(first, first_rank, first_span), (second, second_rank, second_span)] = pair else {
+            unreachable!();
+        };
+
+        if first_rank > second_rank {
+            return Err(Error::misordered_modifiers(
+                (*first_span).clone().merge((*second_span).clone()),
+                first,
+                second,
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub trait CommonParser<T> = Parser<Token, T, Error = Error> + Clone;
 pub type RecursiveParser<'a, T> = Recursive<'a, Token, T, Error>;
 
+/// How much stack each level of [`GrowStack`] wants left before it recurses. A level of the
+/// expression parser takes well over 100 KiB in unoptimized builds.
+const STACK_RED_ZONE: usize = 1024 * 1024;
+/// The size of each stack segment [`GrowStack`] allocates once it runs low.
+const STACK_SEGMENT_SIZE: usize = 8 * 1024 * 1024;
+
+type ParseResult<O> = (
+    Vec<Located<Token, Error>>,
+    Result<(O, Option<Located<Token, Error>>), Located<Token, Error>>,
+);
+
+/// Runs a recursive parser on a fresh stack segment whenever the current one runs low, so
+/// that deeply nested input can't overflow the stack, no matter how big it is.
+///
+/// chumsky doesn't offer a way to wrap a parser's invocation other than implementing its
+/// deprecated, semver-exempt internals, which is why chumsky is pinned to exactly 0.8.0.
+#[derive(Clone)]
+struct GrowStack<P>(P);
+
+#[allow(deprecated)]
+impl<O, P: Parser<Token, O, Error = Error>> Parser<Token, O> for GrowStack<P> {
+    type Error = Error;
+
+    fn parse_inner<D: Debugger>(
+        &self,
+        debugger: &mut D,
+        stream: &mut Stream<Token, Span>,
+    ) -> ParseResult<O> {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, || {
+            debugger.invoke(&self.0, stream)
+        })
+    }
+
+    fn parse_inner_verbose(&self, d: &mut Verbose, s: &mut Stream<Token, Span>) -> ParseResult<O> {
+        self.parse_inner(d, s)
+    }
+
+    fn parse_inner_silent(&self, d: &mut Silent, s: &mut Stream<Token, Span>) -> ParseResult<O> {
+        self.parse_inner(d, s)
+    }
+}
+
 pub fn nested_parser<'a, T: 'a>(
     parser: impl CommonParser<T> + 'a,
     delimiter: Bracket,
@@ -298,17 +629,300 @@ pub fn nested_parser<'a, T: 'a>(
         .boxed()
 }
 
+/// Parses a name, which is either an identifier or a soft keyword such as `where`.
+/// Soft keywords only have a special meaning in certain positions, so they can be used
+/// as names everywhere else, e.g. `let where = 1;`. Hard keywords are never names.
+#[must_use]
+#[allow(clippy::result_large_err)] // chumsky takes errors by value
+pub fn get_identifier_parser() -> impl CommonParser<String> {
+    filter_map(|span, token| match token {
+        Token::Identifier(i) => Ok(i),
+        Token::Keyword(ref kw) => kw
+            .as_identifier_text()
+            .map(ToString::to_string)
+            .ok_or_else(|| Error::keyword_as_identifier(span, kw)),
+        _ => Err(chumsky::Error::expected_input_found(
+            span,
+            None,
+            Some(token),
+        )),
+    })
+    .labelled("identifier")
+}
+
+/// Parses a type that can be used as a bound, which for now is either
+/// a plain identifier or an attribute path such as `mod.Type`.
+#[must_use]
+pub fn get_type_parser() -> impl CommonParser<TypeExpr> {
+    let ident = get_identifier_parser();
+
+    ident
+        .clone()
+        .map(TypeExpr::Ident)
+        .then(just(Token::Dot).ignore_then(ident).repeated())
+        .foldl(|ty, attr| TypeExpr::Attr(Box::new(ty), attr))
+        .labelled("type")
+}
+
+/// Parses `ident (: type (+ type)*)?` into a [`Bound`].
+#[must_use]
+pub fn get_bound_parser() -> impl CommonParser<Bound> {
+    get_identifier_parser()
+        .then(
+            just(Token::Colon)
+                .ignore_then(
+                    get_type_parser()
+                        .separated_by(just(Token::Operator(Operator::Add)))
+                        .at_least(1),
+                )
+                .or_not(),
+        )
+        .map(|(param, bounds)| Bound {
+            param,
+            bounds: bounds.unwrap_or_default(),
+        })
+}
+
+/// Parses an import statement into an [`ImportStmt`]. The trailing semicolon is optional.
+#[must_use]
+pub fn get_import_parser() -> impl CommonParser<ImportStmt> {
+    let path = get_identifier_parser()
+        .separated_by(just(Token::Dot))
+        .at_least(1)
+        .labelled("module path");
+
+    let item = path
+        .clone()
+        .then(
+            just(Token::Keyword(Keyword::As))
+                .ignore_then(get_identifier_parser())
+                .or_not(),
+        )
+        .map(|(path, alias)| ImportItem { path, alias });
+
+    just(Token::Keyword(Keyword::From))
+        .ignore_then(path)
+        .or_not()
+        .then_ignore(just(Token::Keyword(Keyword::Require)))
+        .then(item.separated_by(just(Token::Comma)).at_least(1))
+        .then_ignore(just(Token::Semicolon).or_not())
+        .map(|(from, items)| ImportStmt { from, items })
+}
+
+/// Parses the tokens of a single import statement, e.g. `require a.b as c;`.
+///
+/// # Errors
+/// * The tokens are not an import statement.
+pub fn parse_import(tokens: Vec<(Token, Span)>) -> Result<ImportStmt, Vec<Error>> {
+    ImportStmt::parse(tokens)
+}
+
+/// Parses the tokens of a single binding, e.g. `let mut x = 1;`, `const y = 2;` or
+/// `private let z = 3;`. The trailing semicolon is optional.
+///
+/// # Errors
+/// * The tokens are not a binding, or its modifiers are in the wrong order, e.g. `mut let`.
+pub fn parse_let(tokens: Vec<(Token, Span)>) -> Result<LetBinding, Vec<Error>> {
+    LetBinding::parse(tokens)
+}
+
+/// Reads a decorator such as `@route("/x", methods)` from `cursor`.
+///
+/// A decorator is `@`, its name, and optionally arguments within parentheses. The name may
+/// be a soft keyword, just like other names, see [`get_identifier_parser`].
+///
+/// # Errors
+/// * The next tokens don't form a decorator, or one of its arguments is not a valid
+///   expression. Nothing is consumed in this case.
+#[allow(clippy::result_large_err)]
+pub fn parse_decorator(cursor: &mut Cursor) -> Result<Spanned<Decorator>, Error> {
+    let checkpoint = cursor.save();
+    let decorator = parse_decorator_tokens(cursor);
+
+    if decorator.is_err() {
+        cursor.restore(checkpoint);
+    }
+    decorator
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_decorator_tokens(cursor: &mut Cursor) -> Result<Spanned<Decorator>, Error> {
+    let (_, mut span) = cursor.expect(&Token::At)?.clone();
+
+    let (name, name_span) = match cursor.bump() {
+        Some((Token::Identifier(name), name_span)) => (name.clone(), name_span),
+        Some((token @ Token::Keyword(kw), name_span)) => match kw.as_identifier_text() {
+            Some(name) => (name.to_string(), name_span),
+            None => return Err(Error::unexpected_token(name_span.clone(), token)),
+        },
+        Some((token, span)) => return Err(Error::unexpected_token(span.clone(), token)),
+        None => {
+            return Err(Error::custom(
+                cursor.end_span(),
+                "expected a decorator name",
+            ))
+        }
+    };
+    span = span.merge(name_span.clone());
+
+    if cursor.eat(&Token::StartBracket(Bracket::Paren)).is_none() {
+        return Ok(Spanned::new(Decorator { name, args: None }, span));
+    }
+
+    let mut args = Vec::new();
+    let mut arg = Vec::<(Token, Span)>::new();
+    let mut depth = 0_usize;
+
+    loop {
+        let Some((token, token_span)) = cursor.bump() else {
+            return Err(chumsky::Error::expected_input_found(
+                cursor.end_span(),
+                [Some(Token::EndBracket(Bracket::Paren))],
+                None,
+            ));
+        };
+        span = span.merge(token_span.clone());
+
+        match token {
+            Token::StartBracket(_) => depth += 1,
+            Token::EndBracket(_) if depth > 0 => depth -= 1,
+            Token::EndBracket(Bracket::Paren) | Token::Comma if depth == 0 => {
+                // A trailing comma leaves an empty argument behind, which is fine
+                if !arg.is_empty() {
+                    args.push(parse_argument(core::mem::take(&mut arg))?);
+                } else if token == &Token::Comma {
+                    return Err(Error::unexpected_token(token_span.clone(), token));
+                }
+
+                if token == &Token::Comma {
+                    continue;
+                }
+                break;
+            }
+            _ => (),
+        }
+        arg.push((token.clone(), token_span.clone()));
+    }
+
+    Ok(Spanned::new(
+        Decorator {
+            name,
+            args: Some(args),
+        },
+        span,
+    ))
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_argument(tokens: Vec<(Token, Span)>) -> Result<SpannedExpr, Error> {
+    let span = tokens[0]
+        .1
+        .clone()
+        .merge(tokens[tokens.len() - 1].1.clone());
+
+    Expr::parse(tokens)
+        .map(|expr| Spanned::new(expr, span))
+        .map_err(|mut errors| errors.remove(0))
+}
+
+/// Parses a type from the tokens of `cursor`, which may be generic, e.g. `mod.Type` or
+/// `Vec<Vec<Int>>`. A `>>` which closes two generics at once is split into two `>`, see
+/// [`Cursor::split_current_shift`].
+///
+/// # Errors
+/// * The next tokens don't form a type. Nothing is consumed in this case.
+#[allow(clippy::result_large_err)]
+pub fn parse_type(cursor: &mut Cursor) -> Result<Spanned<TypeExpr>, Error> {
+    let checkpoint = cursor.save();
+    let ty = parse_type_tokens(cursor).and_then(|(ty, extra)| match extra {
+        // The second half of a `>>` after the outermost type, as in `A<B>>`
+        Some(span) => Err(Error::unexpected_token(
+            span,
+            &Token::Operator(Operator::Gt),
+        )),
+        None => Ok(ty),
+    });
+
+    if ty.is_err() {
+        cursor.restore(checkpoint);
+    }
+    ty
+}
+
+/// Parses a type, along with the span of the `>` left over if it was closed by half of a
+/// `>>`, in which case the other half closes the enclosing generic.
+#[allow(clippy::result_large_err)]
+fn parse_type_tokens(cursor: &mut Cursor) -> Result<(Spanned<TypeExpr>, Option<Span>), Error> {
+    let (name, mut span) = parse_type_name(cursor)?;
+    let mut ty = TypeExpr::Ident(name);
+
+    while cursor.eat(&Token::Dot).is_some() {
+        let (attr, attr_span) = parse_type_name(cursor)?;
+        ty = TypeExpr::Attr(Box::new(ty), attr);
+        span = span.merge(attr_span);
+    }
+
+    if cursor.eat(&Token::Operator(Operator::Lt)).is_none() {
+        return Ok((Spanned::new(ty, span), None));
+    }
+
+    let mut args = Vec::new();
+    loop {
+        let (arg, extra) = parse_type_tokens(cursor)?;
+        span = span.merge(arg.span());
+        args.push(arg.into_node());
+
+        let extra = if let Some(close) = extra {
+            span = span.merge(close);
+            None
+        } else if cursor.eat(&Token::Comma).is_some() {
+            continue;
+        } else if let Some((close, extra)) = cursor.split_current_shift() {
+            span = span.merge(close);
+            Some(extra)
+        } else {
+            let (_, close) = cursor.expect(&Token::Operator(Operator::Gt))?;
+            span = span.merge(close.clone());
+            None
+        };
+
+        return Ok((
+            Spanned::new(TypeExpr::Generic(Box::new(ty), args), span),
+            extra,
+        ));
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_type_name(cursor: &mut Cursor) -> Result<(String, Span), Error> {
+    match cursor.bump() {
+        Some((Token::Identifier(name), span)) => Ok((name.clone(), span.clone())),
+        Some((token @ Token::Keyword(kw), span)) => kw
+            .as_identifier_text()
+            .map(|name| (name.to_string(), span.clone()))
+            .ok_or_else(|| Error::unexpected_token(span.clone(), token)),
+        Some((token, span)) => Err(Error::unexpected_token(span.clone(), token)),
+        None => Err(Error::custom(cursor.end_span(), "expected a type")),
+    }
+}
+
 #[must_use]
-#[allow(clippy::too_many_lines, clippy::missing_panics_doc)]
+#[allow(
+    clippy::too_many_lines,
+    clippy::missing_panics_doc,
+    clippy::result_large_err // chumsky takes errors by value
+)]
 pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
     recursive(|body: Recursive<Token, SpannedBody, Error>| {
+        let body = GrowStack(body);
         let e = recursive(|e: Recursive<Token, SpannedExpr, Error>| {
+            let e = GrowStack(e);
             let literal = select! {
                 Token::Literal(lit) => match lit {
                     Literal::Integer(i) => Expr::Integer(i),
-                    Literal::Float(f) => Expr::Float(f),
+                    Literal::Float(f) => Expr::Float(f.raw.into_string()),
                     Literal::String(s) => match s {
-                        StringLiteral::String(s) => Expr::String(s),
+                        StringLiteral::String(s, _) => Expr::String(s),
                         _ => unreachable!(),
                     },
                 }
@@ -316,15 +930,14 @@ pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
             .map_with_span(SpannedExpr::new)
             .labelled("literal");
 
-            let ident = select! {
-                Token::Identifier(s) => match s.as_str() {
+            let ident = get_identifier_parser()
+                .map(|s| match s.as_str() {
                     "true" => Expr::Bool(true),
                     "false" => Expr::Bool(false),
                     _ => Expr::Ident(s),
-                }
-            }
-            .map_with_span(SpannedExpr::new)
-            .labelled("identifier");
+                })
+                .map_with_span(SpannedExpr::new)
+                .labelled("identifier");
 
             let array = e
                 .clone()
@@ -390,12 +1003,13 @@ pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
 
             let atom = choice((
                 literal,
-                ident,
+                ident.clone(),
                 e.clone()
                     .delimited_by(
                         just(Token::StartBracket(Bracket::Paren)),
                         just(Token::EndBracket(Bracket::Paren)),
                     )
+                    .map_with_span(|e, span| SpannedExpr::new(Expr::Group(e), span))
                     .boxed(),
                 if_stmt,
                 while_stmt,
@@ -459,11 +1073,26 @@ pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
                 }
             };
 
+            // `&` is address-of in prefix position, or a mutable reference when followed by `mut`
+            let reference = just(Token::Operator(Operator::BitAnd))
+                .ignore_then(just(Token::Keyword(Keyword::Mut)).or_not())
+                .map_with_span(|r#mut, span| {
+                    SpannedOperator::new(
+                        if r#mut.is_some() {
+                            Operator::RefMut
+                        } else {
+                            Operator::BitAnd
+                        },
+                        span,
+                    )
+                });
+
             let unary = just(Token::Operator(Operator::Sub))
                 .or(just(Token::Operator(Operator::Add)))
                 .or(just(Token::Operator(Operator::Not)))
                 .or(just(Token::Operator(Operator::BitNot)))
                 .map_with_span(spanned_op)
+                .or(reference)
                 .repeated()
                 .then(call.clone())
                 .foldr(|operator, expr| {
@@ -489,20 +1118,34 @@ pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
                 })
                 .boxed();
 
+            // `**` is right-associative, see `Operator::associativity`. Operands are parsed
+            // left-to-right only once and folded from the right afterwards, since backtracking
+            // over `operand **` doubles the work for every nesting level.
             let binary_pow = binary_cast
                 .clone()
-                .then(just(Token::Operator(Operator::Pow)).map_with_span(spanned_op))
-                .repeated()
-                .then(binary_cast)
-                .foldr(|(lhs, operator), rhs| {
-                    let span = lhs.span().merge(rhs.span());
+                .then(
+                    just(Token::Operator(Operator::Pow))
+                        .map_with_span(spanned_op)
+                        .then(binary_cast)
+                        .repeated(),
+                )
+                .map(|(first, rest)| {
+                    let (operators, mut operands): (Vec<_>, Vec<_>) = rest.into_iter().unzip();
+                    operands.insert(0, first);
+
+                    let mut rhs = operands.pop().unwrap();
+                    for (lhs, operator) in operands.into_iter().zip(operators).rev() {
+                        let span = lhs.span().merge(rhs.span());
 
-                    SpannedExpr::new(Expr::BinaryExpr { operator, lhs, rhs }, span)
+                        rhs = SpannedExpr::new(Expr::BinaryExpr { operator, lhs, rhs }, span);
+                    }
+                    rhs
                 })
                 .boxed();
 
             let op = just(Token::Operator(Operator::Mul))
                 .or(just(Token::Operator(Operator::Div)))
+                .or(just(Token::Operator(Operator::FloorDiv)))
                 .or(just(Token::Operator(Operator::Mod)))
                 .map_with_span(spanned_op);
             let binary_product = binary_pow
@@ -578,45 +1221,64 @@ pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
             let op = just(Token::Operator(Operator::BitAnd))
                 .or(just(Token::Operator(Operator::BitOr)))
                 .or(just(Token::Operator(Operator::BitXor)))
+                .or(just(Token::Operator(Operator::BitLShift)))
+                .or(just(Token::Operator(Operator::BitRShift)))
                 .map_with_span(spanned_op);
-            binary_logical_or
+            let binary_bitwise = binary_logical_or
                 .clone()
                 .then(op.then(binary_logical_or).repeated())
                 .foldl(|lhs, (operator, rhs)| {
                     let span = lhs.span().merge(rhs.span());
 
                     Spanned::new(Expr::BinaryExpr { operator, lhs, rhs }, span)
+                });
+
+            // `..` followed by an optional end, which makes `a..b`, `a..`, `..b` and `..`
+            let range_end =
+                just(Token::Operator(Operator::Range)).ignore_then(binary_bitwise.clone().or_not());
+
+            binary_bitwise
+                .then(range_end.clone().or_not())
+                .map_with_span(|(start, end), span| match end {
+                    Some(end) => Spanned::new(
+                        Expr::Range {
+                            start: Some(start),
+                            end,
+                        },
+                        span,
+                    ),
+                    None => start,
                 })
+                .or(range_end.map_with_span(|end, span| {
+                    Spanned::new(Expr::Range { start: None, end }, span)
+                }))
                 .boxed()
         });
 
         let require = just::<_, Token, _>(Token::Keyword(Keyword::Require))
             .ignore_then(
-                select! {
-                    Token::Identifier(i) => i,
-                }
-                .separated_by(just::<_, Token, _>(Token::Comma))
-                .allow_trailing()
-                .at_least(1),
+                get_identifier_parser()
+                    .separated_by(just::<_, Token, _>(Token::Comma))
+                    .allow_trailing()
+                    .at_least(1),
             )
             .then_ignore(just::<_, Token, _>(Token::Semicolon))
             .map_with_span(|n, span| Spanned::new(Node::Require(n), span));
 
         // TODO: support more targets
         let target = recursive(|t| {
-            select!(
-                Token::Identifier(i) => Target::Ident(i),
-            )
-            .map_with_span(Spanned::new)
-            .or(t
-                .separated_by(just::<_, Token, _>(Token::Comma))
-                .allow_trailing()
-                .at_least(1)
-                .delimited_by(
-                    just(Token::StartBracket(Bracket::Bracket)),
-                    just(Token::EndBracket(Bracket::Bracket)),
-                )
-                .map_with_span(|a, span| Spanned::new(Target::Array(a), span)))
+            get_identifier_parser()
+                .map(Target::Ident)
+                .map_with_span(Spanned::new)
+                .or(t
+                    .separated_by(just::<_, Token, _>(Token::Comma))
+                    .allow_trailing()
+                    .at_least(1)
+                    .delimited_by(
+                        just(Token::StartBracket(Bracket::Bracket)),
+                        just(Token::EndBracket(Bracket::Bracket)),
+                    )
+                    .map_with_span(|a, span| Spanned::new(Target::Array(a), span)))
         });
 
         let declare = just::<_, Token, _>(Token::Keyword(Keyword::Let))
@@ -680,9 +1342,7 @@ pub fn get_body_parser<'a>() -> RecursiveParser<'a, SpannedBody> {
             .map_with_span(|(target, default), span| Spanned::new(Param { target, default }, span));
 
         let func = just::<_, Token, _>(Token::Keyword(Keyword::Func))
-            .ignore_then(select! {
-                Token::Identifier(i) => i,
-            })
+            .ignore_then(get_identifier_parser())
             .then(
                 param
                     .separated_by(just::<_, Token, _>(Token::Comma))
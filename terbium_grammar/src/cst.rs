@@ -0,0 +1,207 @@
+use super::token::{get_lexer, Bracket, Token};
+use super::{Error, Source, Span};
+
+use chumsky::Parser;
+use core::ops::Range;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Token(Token),
+    /// Whitespace and comments.
+    Trivia,
+}
+
+impl SyntaxKind {
+    /// Whether this is whitespace or a comment rather than a token the parser cares about.
+    #[must_use]
+    pub const fn is_trivia(&self) -> bool {
+        matches!(self, Self::Trivia)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Root,
+    /// Everything from an opening bracket up to and including its closing bracket.
+    Group(Bracket),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub text: String,
+    pub range: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxElement {
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Self::Node(node) => node.range.clone(),
+            Self::Token(token) => token.range.clone(),
+        }
+    }
+
+    fn write_text(&self, out: &mut String) {
+        match self {
+            Self::Node(node) => node.children.iter().for_each(|child| child.write_text(out)),
+            Self::Token(token) => out.push_str(&token.text),
+        }
+    }
+}
+
+/// A node of the lossless concrete syntax tree, which keeps every character of the source.
+///
+/// Unlike the AST, whitespace and comments are kept as trivia tokens, so that formatters and
+/// refactoring tools can reproduce the source exactly. Tokens are grouped into nodes by their
+/// brackets. All offsets are char offsets, same as those of [`Span`]s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    pub range: Range<usize>,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    const fn new(kind: NodeKind, start: usize) -> Self {
+        Self {
+            kind,
+            range: start..start,
+            children: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, element: SyntaxElement) {
+        self.range.end = element.range().end;
+        self.children.push(element);
+    }
+
+    /// Tokenizes `src` and builds the tree for it.
+    ///
+    /// # Errors
+    /// * The source does not match Terbium grammar.
+    pub fn from_source(src: &str) -> Result<Self, Vec<Error>> {
+        let source = Source::default();
+        let chars = src.chars().collect::<Vec<_>>();
+
+        let tokens = get_lexer().parse(chumsky::Stream::<_, Span, _>::from_iter(
+            Span::single(source.clone(), chars.len()),
+            chars
+                .iter()
+                .enumerate()
+                .map(|(i, c)| (*c, Span::single(source.clone(), i))),
+        ))?;
+
+        Ok(Self::from_tokens(&chars, tokens))
+    }
+
+    /// Builds the tree from the tokens of `chars`, filling the gaps between them with trivia.
+    #[must_use]
+    pub fn from_tokens(chars: &[char], tokens: Vec<(Token, Span)>) -> Self {
+        let text = |range: Range<usize>| chars[range].iter().collect::<String>();
+        let trivia = |range: Range<usize>| {
+            SyntaxElement::Token(SyntaxToken {
+                kind: SyntaxKind::Trivia,
+                text: text(range.clone()),
+                range,
+            })
+        };
+
+        let mut stack = vec![Self::new(NodeKind::Root, 0)];
+        let mut offset = 0;
+
+        for (token, span) in tokens {
+            if span.start() > offset {
+                stack.last_mut().unwrap().push(trivia(offset..span.start()));
+            }
+            offset = span.end();
+
+            if let Token::StartBracket(bracket) = token {
+                stack.push(Self::new(NodeKind::Group(bracket), span.start()));
+            }
+
+            let closes = matches!(
+                (&token, stack.last().unwrap().kind),
+                (Token::EndBracket(end), NodeKind::Group(start)) if *end == start
+            );
+
+            stack
+                .last_mut()
+                .unwrap()
+                .push(SyntaxElement::Token(SyntaxToken {
+                    kind: SyntaxKind::Token(token),
+                    text: text(span.range()),
+                    range: span.range(),
+                }));
+
+            if closes {
+                let group = stack.pop().unwrap();
+                stack.last_mut().unwrap().push(SyntaxElement::Node(group));
+            }
+        }
+
+        // Unclosed groups simply end with the source
+        while stack.len() > 1 {
+            let group = stack.pop().unwrap();
+            stack.last_mut().unwrap().push(SyntaxElement::Node(group));
+        }
+
+        let mut root = stack.pop().unwrap();
+        if chars.len() > offset {
+            root.push(trivia(offset..chars.len()));
+        }
+
+        root
+    }
+
+    /// The exact source text this node was built from.
+    #[must_use]
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.children
+            .iter()
+            .for_each(|child| child.write_text(&mut out));
+
+        out
+    }
+
+    /// Iterates over the tokens of this node in source order, including trivia.
+    pub fn tokens(&self) -> Box<dyn Iterator<Item = &SyntaxToken> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            SyntaxElement::Node(node) => node.tokens(),
+            SyntaxElement::Token(token) => Box::new(std::iter::once(token)),
+        }))
+    }
+
+    /// Iterates over the nodes nested in this one, depth-first.
+    pub fn descendants(&self) -> Box<dyn Iterator<Item = &Self> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            SyntaxElement::Node(node) => Box::new(std::iter::once(node).chain(node.descendants()))
+                as Box<dyn Iterator<Item = _>>,
+            SyntaxElement::Token(_) => Box::new(std::iter::empty()),
+        }))
+    }
+}
+
+/// Drops the trivia from `tokens`, e.g. those of [`SyntaxNode::tokens`], leaving the same
+/// tokens and spans that [`get_lexer`] produces for the source, so that the parser can consume
+/// them.
+#[must_use]
+pub fn strip_trivia<'a>(tokens: impl IntoIterator<Item = &'a SyntaxToken>) -> Vec<(Token, Span)> {
+    tokens
+        .into_iter()
+        .filter_map(|token| match &token.kind {
+            SyntaxKind::Token(t) => Some((
+                t.clone(),
+                Span::from_range(Source::default(), token.range.clone()),
+            )),
+            SyntaxKind::Trivia => None,
+        })
+        .collect()
+}
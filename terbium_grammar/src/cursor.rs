@@ -0,0 +1,155 @@
+use super::token::{Operator, Token};
+use super::{Error, ErrorKind, Span};
+
+use core::mem::Discriminant;
+
+/// A position within the tokens of a [`Cursor`], which can be returned to with
+/// [`Cursor::restore`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// Walks over tokens one at a time, for use by hand-written recursive descent parsers.
+#[derive(Clone, Debug)]
+pub struct Cursor<'a> {
+    tokens: &'a [(Token, Span)],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    #[must_use]
+    pub const fn new(tokens: &'a [(Token, Span)]) -> Self {
+        Self { tokens, pos: 0 }
+    }
+
+    /// The index of the next token.
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+
+    #[must_use]
+    pub const fn is_at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// The next token without consuming it.
+    #[must_use]
+    pub fn peek(&self) -> Option<&'a (Token, Span)> {
+        self.peek_nth(0)
+    }
+
+    /// The token `n` tokens after the next one without consuming anything,
+    /// i.e. `peek_nth(0)` is the same as `peek()`.
+    #[must_use]
+    pub fn peek_nth(&self, n: usize) -> Option<&'a (Token, Span)> {
+        self.tokens.get(self.pos + n)
+    }
+
+    /// The kind of the next token, see [`Token::kind`].
+    #[must_use]
+    pub fn peek_kind(&self) -> Option<Discriminant<Token>> {
+        self.peek().map(|(token, _)| token.kind())
+    }
+
+    /// Consumes the next token.
+    pub fn bump(&mut self) -> Option<&'a (Token, Span)> {
+        let next = self.peek()?;
+        self.pos += 1;
+
+        Some(next)
+    }
+
+    /// Consumes the next token only if it is equal to `token`.
+    pub fn eat(&mut self, token: &Token) -> Option<&'a (Token, Span)> {
+        match self.peek() {
+            Some((next, _)) if next == token => self.bump(),
+            _ => None,
+        }
+    }
+
+    /// Consumes the next token, which must be equal to `token`.
+    ///
+    /// # Errors
+    /// * The next token is a different token, or there are no tokens left.
+    ///   Nothing is consumed in this case.
+    // Returns the same error type as the chumsky parsers, so that errors of hand-written
+    // parsers can be reported along with theirs
+    #[allow(clippy::result_large_err)]
+    pub fn expect(&mut self, token: &Token) -> Result<&'a (Token, Span), Error> {
+        if let Some(next) = self.eat(token) {
+            return Ok(next);
+        }
+
+        let expected = Some(token.clone());
+        Err(match self.peek() {
+            Some((found, span)) => {
+                chumsky::Error::expected_input_found(span.clone(), [expected], Some(found.clone()))
+            }
+            None => chumsky::Error::expected_input_found(self.end_span(), [expected], None),
+        })
+    }
+
+    /// Consumes the next token only if it is of the given kind, see [`Token::kind`].
+    pub fn eat_kind(&mut self, kind: Discriminant<Token>) -> Option<&'a (Token, Span)> {
+        match self.peek() {
+            Some((next, _)) if next.kind() == kind => self.bump(),
+            _ => None,
+        }
+    }
+
+    /// Consumes the next token, which must be of the given kind, see [`Token::kind`].
+    ///
+    /// # Errors
+    /// * The next token is of a different kind, or there are no tokens left.
+    ///   Nothing is consumed in this case.
+    #[allow(clippy::result_large_err)]
+    pub fn expect_kind(&mut self, kind: Discriminant<Token>) -> Result<&'a (Token, Span), Error> {
+        if let Some(next) = self.eat_kind(kind) {
+            return Ok(next);
+        }
+
+        Err(match self.peek() {
+            Some((found, span)) => Error::unexpected_token(span.clone(), found),
+            None => Error {
+                kind: ErrorKind::UnexpectedEnd,
+                ..Error::custom(self.end_span(), "unexpected end of input")
+            },
+        })
+    }
+
+    /// Consumes the next token only if it is `>>`, and returns the spans of the two `>` it
+    /// is made of. The lexer always lexes `>>` as a shift, so a parser which closes two
+    /// generics at once, as in `Vec<Vec<Int>>`, splits it instead.
+    pub fn split_current_shift(&mut self) -> Option<(Span, Span)> {
+        let (_, span) = self.eat(&Token::Operator(Operator::BitRShift))?;
+        let mid = span.start() + 1;
+
+        Some((
+            Span::from_range(span.src(), span.start()..mid),
+            Span::from_range(span.src(), mid..span.end()),
+        ))
+    }
+
+    /// Saves the current position, e.g. before a speculative parse.
+    #[must_use]
+    pub const fn save(&self) -> Checkpoint {
+        Checkpoint(self.pos)
+    }
+
+    /// Returns to a position saved by [`Cursor::save`], e.g. after a speculative parse failed.
+    pub const fn restore(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
+    }
+
+    /// The tokens which have not been consumed yet.
+    #[must_use]
+    pub fn remaining(&self) -> &'a [(Token, Span)] {
+        &self.tokens[self.pos.min(self.tokens.len())..]
+    }
+
+    pub(crate) fn end_span(&self) -> Span {
+        self.tokens.last().map_or_else(Span::default, |(_, span)| {
+            Span::single(span.src(), span.end())
+        })
+    }
+}
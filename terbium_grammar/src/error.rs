@@ -1,12 +1,15 @@
-use super::{Source, Span, Token};
+use super::token::Keyword;
+#[cfg(feature = "std")]
+use super::Source;
+use super::{Operator, Span, Token};
 
-use std::collections::HashSet;
-use std::fmt::{Display, Formatter, Result as FmtResult};
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
 use std::io::Write;
 use std::string::ToString;
 
-use ariadne;
-
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub enum TargetKind {
     Char(char),
@@ -61,15 +64,95 @@ pub enum HintAction {
     None,
 }
 
+/// A suggestion for fixing an error. Unlike error messages, hints have no templates and are
+/// always in English, so [`Error::localize`] leaves them as they are.
 #[derive(Debug, PartialEq, Eq)]
 pub struct Hint {
     pub message: String,
     pub action: HintAction,
 }
 
+/// How severe a diagnostic is. Warnings are reported alongside errors,
+/// but they do not indicate that the source is invalid.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A stable identifier for each kind of error message, which can be used to look up
+/// translations of the message through [`Messages`].
+#[repr(u16)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    Custom = 0,
+    ExpectedFound = 1,
+    UnexpectedToken = 2,
+    UnclosedDelimiter = 3,
+    ConstMut = 4,
+    ReservedIdentifier = 5,
+    RedundantParens = 6,
+    ChainedComparison = 7,
+    KeywordAsIdentifier = 8,
+    MisorderedModifiers = 9,
+    MisplacedDollar = 10,
+    MisplacedHash = 11,
+}
+
+impl ErrorCode {
+    /// The English message template for this code. `{0}`, `{1}` and so on are
+    /// replaced by the arguments of the error.
+    #[must_use]
+    pub const fn default_template(self) -> &'static str {
+        match self {
+            Self::Custom => "{0}",
+            Self::ExpectedFound => "expected {0}, found {1} instead",
+            Self::UnexpectedToken => "unexpected token {0}",
+            Self::UnclosedDelimiter => "unclosed delimiter: expected {0}",
+            Self::ConstMut => "cannot declare as 'const mut'",
+            Self::ReservedIdentifier => "'{0}' is reserved and may become a keyword in the future",
+            Self::RedundantParens => "redundant parentheses",
+            Self::ChainedComparison => "comparison operators '{0}' and '{1}' are chained",
+            Self::KeywordAsIdentifier => "'{0}' is a keyword and can't be used as an identifier",
+            Self::MisorderedModifiers => "'{1}' must come before '{0}'",
+            Self::MisplacedDollar => {
+                "'$' is only valid as a string interpolation prefix, e.g. $\"{x}\""
+            }
+            Self::MisplacedHash => "'#' is only valid in a raw identifier, e.g. r#func",
+        }
+    }
+
+    #[must_use]
+    fn render(template: &str, args: &[String]) -> String {
+        args.iter()
+            .enumerate()
+            .fold(template.to_string(), |message, (i, arg)| {
+                message.replace(&format!("{{{}}}", i), arg)
+            })
+    }
+}
+
+/// A table of message templates, e.g. translations, which can override
+/// the default English messages of errors. See [`Error::localize`].
+pub trait Messages {
+    /// The template for the given code, or `None` to keep the default message.
+    fn template(&self, code: ErrorCode) -> Option<Cow<'_, str>>;
+}
+
+impl Messages for HashMap<ErrorCode, String> {
+    fn template(&self, code: ErrorCode) -> Option<Cow<'_, str>> {
+        self.get(&code)
+            .map(|template| Cow::Borrowed(template.as_str()))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Error {
     pub kind: ErrorKind,
+    pub severity: Severity,
+    pub code: ErrorCode,
+    /// The values which are filled into the message template.
+    pub args: Vec<String>,
     pub span: Span,
     pub expected: HashSet<TargetKind>,
     pub label: Option<&'static str>,
@@ -78,61 +161,216 @@ pub struct Error {
 }
 
 impl Error {
-    #[must_use]
-    pub fn placeholder() -> Self {
+    /// An error whose message is rendered from the default template of `code`, so that
+    /// [`Error::localize`] can render it the same way from any other template.
+    fn new(kind: ErrorKind, code: ErrorCode, args: Vec<String>, span: Span) -> Self {
         Self {
-            kind: ErrorKind::Custom,
-            span: Span::default(),
+            kind,
+            severity: Severity::Error,
+            code,
+            message: ErrorCode::render(code.default_template(), &args),
+            args,
+            span,
             expected: HashSet::new(),
             label: None,
-            message: String::new(),
             hint: None,
         }
     }
 
+    #[must_use]
+    pub fn placeholder() -> Self {
+        Self::new(
+            ErrorKind::Custom,
+            ErrorCode::Custom,
+            vec![String::new()],
+            Span::default(),
+        )
+    }
+
     #[must_use]
     pub fn custom(span: Span, message: impl Display) -> Self {
-        Self {
-            kind: ErrorKind::Custom,
+        Self::new(
+            ErrorKind::Custom,
+            ErrorCode::Custom,
+            vec![message.to_string()],
             span,
-            expected: HashSet::new(),
-            label: None,
-            message: message.to_string(),
-            hint: None,
-        }
+        )
     }
 
     #[must_use]
     pub fn unexpected_token(span: Span, token: &Token) -> Self {
-        Self {
-            kind: ErrorKind::Unexpected(TargetKind::Token(token.clone())),
+        Self::new(
+            ErrorKind::Unexpected(TargetKind::Token(token.clone())),
+            ErrorCode::UnexpectedToken,
+            vec![token.to_string()],
             span,
-            expected: HashSet::new(),
-            label: None,
-            message: format!("unexpected token {}", token),
-            hint: None,
-        }
+        )
+    }
+
+    /// A `$` which doesn't start an interpolated string, e.g. `1 $ 2`.
+    #[must_use]
+    pub fn misplaced_dollar(span: Span) -> Self {
+        Self::new(
+            ErrorKind::Unexpected(TargetKind::Token(Token::Invalid('$'))),
+            ErrorCode::MisplacedDollar,
+            Vec::new(),
+            span,
+        )
+    }
+
+    /// A `#` which isn't part of a raw identifier, e.g. `#x`.
+    #[must_use]
+    pub fn misplaced_hash(span: Span) -> Self {
+        Self::new(
+            ErrorKind::Unexpected(TargetKind::Token(Token::Invalid('#'))),
+            ErrorCode::MisplacedHash,
+            Vec::new(),
+            span,
+        )
     }
 
     #[must_use]
     pub fn no_const_mut(span: Span) -> Self {
         Self {
-            kind: ErrorKind::Custom,
-            span,
-            expected: HashSet::new(),
-            label: None,
-            message: "cannot declare as 'const mut'".to_string(),
             hint: Some(Hint {
                 message: "consider using 'let mut' instead".to_string(),
                 action: HintAction::Replace("let mut".to_string()),
             }),
+            ..Self::new(ErrorKind::Custom, ErrorCode::ConstMut, Vec::new(), span)
+        }
+    }
+
+    #[must_use]
+    pub fn reserved_identifier(span: Span, name: &str) -> Self {
+        Self {
+            severity: Severity::Warning,
+            hint: Some(Hint {
+                message: "consider renaming this identifier".to_string(),
+                action: HintAction::None,
+            }),
+            ..Self::new(
+                ErrorKind::Custom,
+                ErrorCode::ReservedIdentifier,
+                vec![name.to_string()],
+                span,
+            )
         }
     }
 
+    #[must_use]
+    pub fn redundant_parens(span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            hint: Some(Hint {
+                message: "removing them does not change the meaning of the expression".to_string(),
+                action: HintAction::None,
+            }),
+            ..Self::new(
+                ErrorKind::Custom,
+                ErrorCode::RedundantParens,
+                Vec::new(),
+                span,
+            )
+        }
+    }
+
+    /// A keyword where an identifier was expected, e.g. `let func = 1`.
+    #[must_use]
+    pub fn keyword_as_identifier(span: Span, keyword: &Keyword) -> Self {
+        Self {
+            expected: core::iter::once(TargetKind::Identifier).collect(),
+            hint: Some(Hint {
+                message: format!("use 'r#{}' to use it as an identifier", keyword),
+                action: HintAction::Replace(format!("r#{}", keyword)),
+            }),
+            ..Self::new(
+                ErrorKind::Unexpected(TargetKind::Token(Token::Keyword(keyword.clone()))),
+                ErrorCode::KeywordAsIdentifier,
+                vec![keyword.to_string()],
+                span,
+            )
+        }
+    }
+
+    /// `a < b < c`, which compares the result of `a < b` with `c` rather than checking whether
+    /// `b` lies between `a` and `c`.
+    #[must_use]
+    pub fn chained_comparison(span: Span, first: Operator, second: Operator) -> Self {
+        Self {
+            severity: Severity::Warning,
+            hint: Some(Hint {
+                message: "use '&&' to compare both sides, e.g. 'a < b && b < c', or add \
+                          parentheses if comparing the result is intended"
+                    .to_string(),
+                action: HintAction::None,
+            }),
+            ..Self::new(
+                ErrorKind::Custom,
+                ErrorCode::ChainedComparison,
+                vec![first.to_string(), second.to_string()],
+                span,
+            )
+        }
+    }
+
+    /// Two binding modifiers in the wrong order, e.g. `mut let` rather than `let mut`.
+    #[must_use]
+    pub fn misordered_modifiers(span: Span, first: &Keyword, second: &Keyword) -> Self {
+        Self {
+            hint: Some(Hint {
+                message: format!("consider using '{} {}' instead", second, first),
+                action: HintAction::Replace(format!("{} {}", second, first)),
+            }),
+            ..Self::new(
+                ErrorKind::Custom,
+                ErrorCode::MisorderedModifiers,
+                vec![first.to_string(), second.to_string()],
+                span,
+            )
+        }
+    }
+
+    #[must_use]
+    pub fn span(&self) -> Span {
+        self.span.clone()
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /// The targets that would have been valid where this error occurred,
+    /// sorted by their display form so that the order is deterministic.
+    #[must_use]
+    pub fn expected(&self) -> Vec<&TargetKind> {
+        let mut expected = self.expected.iter().collect::<Vec<_>>();
+        expected.sort_by_cached_key(ToString::to_string);
+
+        expected
+    }
+
+    /// Re-renders the message of this error from the template that `messages` has for its
+    /// code, e.g. to translate it. The code and arguments of the error stay the same, and so
+    /// does its [`Hint`].
+    pub fn localize(&mut self, messages: &impl Messages) {
+        let template = messages
+            .template(self.code)
+            .unwrap_or(Cow::Borrowed(self.code.default_template()));
+
+        self.message = ErrorCode::render(&template, &self.args);
+    }
+
+    #[must_use]
+    pub const fn is_warning(&self) -> bool {
+        matches!(self.severity, Severity::Warning)
+    }
+
     /// Write the error to the specified writer.
     ///
     /// # Panics
     /// * Panic when writing to writer failed.
+    #[cfg(feature = "std")]
     pub fn write<C>(self, cache: C, writer: impl Write)
     where
         C: ariadne::Cache<Source>,
@@ -142,9 +380,14 @@ impl Error {
         let mut colors = ColorGenerator::new();
         let primary = colors.next();
 
-        let report = Report::build(ReportKind::Error, self.span.src(), self.span.start())
-            .with_code(0)
-            .with_message("invalid syntax")
+        let (kind, message) = match self.severity {
+            Severity::Error => (ReportKind::Error, "invalid syntax"),
+            Severity::Warning => (ReportKind::Warning, "suspicious syntax"),
+        };
+
+        let report = Report::build(kind, self.span.src(), self.span.start())
+            .with_code(self.code as u16)
+            .with_message(message)
             .with_label(
                 Label::new(self.span.clone())
                     .with_message(self.message)
@@ -180,28 +423,23 @@ impl<T: Into<TargetKind> + Clone> chumsky::Error<T> for Error {
             .map(ToString::to_string)
             .collect::<Vec<String>>();
 
-        let joined;
-        let expected_message = if expected_message.len() == 1 {
-            expected_message.first().unwrap().as_str()
-        } else {
-            joined = expected_message.join(" or ");
-            joined.as_str()
-        };
+        let args = vec![
+            expected_message.join(" or "),
+            found
+                .clone()
+                .map_or_else(String::new, |found| found.into().to_string()),
+        ];
 
         Self {
-            kind: found
-                .clone()
-                .map(Into::into)
-                .map_or(ErrorKind::UnexpectedEnd, ErrorKind::Unexpected),
-            span,
             expected,
-            label: None,
-            message: format!(
-                "expected {}, found {} instead",
-                expected_message,
-                found.map_or_else(String::new, |found| found.into().to_string())
-            ),
-            hint: None,
+            ..Self::new(
+                found
+                    .map(Into::into)
+                    .map_or(ErrorKind::UnexpectedEnd, ErrorKind::Unexpected),
+                ErrorCode::ExpectedFound,
+                args,
+                span,
+            )
         }
     }
 
@@ -212,22 +450,24 @@ impl<T: Into<TargetKind> + Clone> chumsky::Error<T> for Error {
         expected: T,
         before: Option<T>,
     ) -> Self {
+        let delimiter = expected.clone().into().to_string();
+
         Self {
-            kind: ErrorKind::Unclosed {
-                start: start.into(),
-                span: before_span,
-                before: before.map(Into::into),
-            },
-            span,
-            expected: std::iter::once(expected.clone().into()).collect(),
-            label: None,
-            message: format!("unclosed delimiter: expected {}", {
-                expected.clone().into().to_string()
-            }),
+            expected: core::iter::once(expected.into()).collect(),
             hint: Some(Hint {
                 message: "add the missing delimiter".to_string(),
-                action: HintAction::Insert(expected.into().to_string()),
+                action: HintAction::Insert(delimiter.clone()),
             }),
+            ..Self::new(
+                ErrorKind::Unclosed {
+                    start: start.into(),
+                    span: before_span,
+                    before: before.map(Into::into),
+                },
+                ErrorCode::UnclosedDelimiter,
+                vec![delimiter],
+                span,
+            )
         }
     }
 
@@ -243,6 +483,9 @@ impl<T: Into<TargetKind> + Clone> chumsky::Error<T> for Error {
 
         Self {
             kind: self.kind,
+            severity: self.severity,
+            code: self.code,
+            args: self.args,
             span: self.span.merge(other.span),
             expected: self.expected,
             label: self.label,
@@ -0,0 +1,350 @@
+use super::ast::{Body, Expr, Node, SpannedExpr, SpannedNode};
+use super::Operator;
+
+/// Evaluates the parts of `expr` which only contain literals, e.g. `1 + 2 * 3` becomes `7`
+/// and `a + (1 + 2)` becomes `a + 3`.
+///
+/// Integer arithmetic, bitwise and comparison operators, boolean logic and string
+/// concatenation through `+` are folded. Anything that would fail at runtime, such as an
+/// overflow or `1 % 0`, is left as is so that the error surfaces when the code runs.
+/// `/` is never folded, since whether it divides integers exactly is up to the runtime.
+#[must_use]
+pub fn fold_constants(expr: Expr) -> Expr {
+    match expr {
+        Expr::Group(inner) => {
+            let inner = inner.map(fold_constants);
+
+            // The parentheses don't mean anything around a single value
+            if is_constant(inner.node()) {
+                inner.into_node()
+            } else {
+                Expr::Group(inner)
+            }
+        }
+        Expr::UnaryExpr { operator, value } => {
+            let value = value.map(fold_constants);
+
+            match (operator.node(), value.node()) {
+                (Operator::Add, Expr::Integer(i)) => Expr::Integer(*i),
+                (Operator::Not, Expr::Bool(b)) => Expr::Bool(!b),
+                _ => Expr::UnaryExpr { operator, value },
+            }
+        }
+        Expr::BinaryExpr { operator, lhs, rhs } => {
+            let lhs = lhs.map(fold_constants);
+            let rhs = rhs.map(fold_constants);
+
+            fold_binary(*operator.node(), lhs.node(), rhs.node()).unwrap_or(Expr::BinaryExpr {
+                operator,
+                lhs,
+                rhs,
+            })
+        }
+        Expr::Array(items) => Expr::Array(fold_all(items)),
+        Expr::Cast(subject, ty) => Expr::Cast(subject.map(fold_constants), ty),
+        Expr::Attr(value, attr) => Expr::Attr(value.map(fold_constants), attr),
+        Expr::Call {
+            value,
+            args,
+            kwargs,
+        } => Expr::Call {
+            value: value.map(fold_constants),
+            args: fold_all(args),
+            kwargs: kwargs
+                .into_iter()
+                .map(|(name, arg)| (name, arg.map(fold_constants)))
+                .collect(),
+        },
+        Expr::If {
+            condition,
+            body,
+            else_if_bodies,
+            else_body,
+        } => Expr::If {
+            condition: condition.map(fold_constants),
+            body: body.map(fold_body),
+            else_if_bodies: else_if_bodies
+                .into_iter()
+                .map(|(condition, body)| (condition.map(fold_constants), body.map(fold_body)))
+                .collect(),
+            else_body: else_body.map(|body| body.map(fold_body)),
+        },
+        Expr::While { condition, body } => Expr::While {
+            condition: condition.map(fold_constants),
+            body: fold_nodes(body),
+        },
+        Expr::Range { start, end } => Expr::Range {
+            start: start.map(|start| start.map(fold_constants)),
+            end: end.map(|end| end.map(fold_constants)),
+        },
+        e @ (Expr::Integer(_)
+        | Expr::Float(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Ident(_)) => e,
+    }
+}
+
+const fn is_constant(expr: &Expr) -> bool {
+    matches!(expr, Expr::Integer(_) | Expr::Bool(_) | Expr::String(_))
+}
+
+fn fold_binary(operator: Operator, lhs: &Expr, rhs: &Expr) -> Option<Expr> {
+    Some(match (lhs, rhs) {
+        (Expr::Integer(a), Expr::Integer(b)) => {
+            let (a, b) = (*a, *b);
+            // Integers are `i128`s at runtime, so anything larger is left for it to report
+            let int = |result: Option<u128>| {
+                result
+                    .filter(|&i| i128::try_from(i).is_ok())
+                    .map(Expr::Integer)
+            };
+
+            match operator {
+                Operator::Add => int(a.checked_add(b))?,
+                Operator::Sub => int(a.checked_sub(b))?,
+                Operator::Mul => int(a.checked_mul(b))?,
+                Operator::FloorDiv => int(a.checked_div(b))?,
+                Operator::Mod => int(a.checked_rem(b))?,
+                Operator::Pow => int(a.checked_pow(b.try_into().ok()?))?,
+                Operator::BitOr => int(Some(a | b))?,
+                Operator::BitXor => int(Some(a ^ b))?,
+                Operator::BitAnd => int(Some(a & b))?,
+                Operator::BitLShift => int(a.checked_shl(b.try_into().ok()?))?,
+                Operator::BitRShift => int(a.checked_shr(b.try_into().ok()?))?,
+                Operator::Eq => Expr::Bool(a == b),
+                Operator::Ne => Expr::Bool(a != b),
+                Operator::Lt => Expr::Bool(a < b),
+                Operator::Le => Expr::Bool(a <= b),
+                Operator::Gt => Expr::Bool(a > b),
+                Operator::Ge => Expr::Bool(a >= b),
+                _ => return None,
+            }
+        }
+        (Expr::Bool(a), Expr::Bool(b)) => Expr::Bool(match operator {
+            Operator::And => *a && *b,
+            Operator::Or => *a || *b,
+            Operator::Eq => a == b,
+            Operator::Ne => a != b,
+            _ => return None,
+        }),
+        (Expr::String(a), Expr::String(b)) => match operator {
+            Operator::Add => Expr::String(a.clone() + b),
+            Operator::Eq => Expr::Bool(a == b),
+            Operator::Ne => Expr::Bool(a != b),
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+fn fold_all(exprs: Vec<SpannedExpr>) -> Vec<SpannedExpr> {
+    exprs
+        .into_iter()
+        .map(|expr| expr.map(fold_constants))
+        .collect()
+}
+
+fn fold_body(Body(nodes, return_last): Body) -> Body {
+    Body(fold_nodes(nodes), return_last)
+}
+
+fn fold_nodes(nodes: Vec<SpannedNode>) -> Vec<SpannedNode> {
+    nodes
+        .into_iter()
+        .map(|node| {
+            node.map(|node| match node {
+                Node::Module(nodes) => Node::Module(fold_nodes(nodes)),
+                Node::Func {
+                    name,
+                    params,
+                    body,
+                    return_last,
+                } => Node::Func {
+                    name,
+                    params,
+                    body: fold_nodes(body),
+                    return_last,
+                },
+                Node::Expr(value) => Node::Expr(value.map(fold_constants)),
+                Node::Declare {
+                    targets,
+                    value,
+                    r#mut,
+                    r#const,
+                } => Node::Declare {
+                    targets,
+                    value: value.map(fold_constants),
+                    r#mut,
+                    r#const,
+                },
+                Node::Assign { targets, value } => Node::Assign {
+                    targets,
+                    value: value.map(fold_constants),
+                },
+                Node::Return(value) => Node::Return(value.map(|value| value.map(fold_constants))),
+                node @ Node::Require(_) => node,
+            })
+        })
+        .collect()
+}
+
+/// The value of a constant expression, see [`eval_const`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConstValue {
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+/// Why a constant expression could not be evaluated, see [`eval_const`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EvalError {
+    /// The expression contains something other than literals and operators, e.g. a name
+    /// or a call.
+    NotConstant,
+    /// The operator can't be used on values of these types, e.g. `1 + "a"`.
+    InvalidOperands(Operator),
+    DivisionByZero,
+    /// The result doesn't fit in an `i128`.
+    Overflow,
+}
+
+/// Evaluates `expr`, which must only contain literals and operators, e.g. the value of
+/// `const X = 2 + 3 * 4`.
+///
+/// Integers are `i128`s and floats are `f64`s. An integer used along with a float is
+/// converted into a float first. `/` truncates when both sides are integers, just as it
+/// does at runtime, and `&&` and `||` only evaluate their right-hand side when needed.
+///
+/// # Errors
+/// * The expression isn't constant, see [`EvalError`] for what else can go wrong.
+pub fn eval_const(expr: &Expr) -> Result<ConstValue, EvalError> {
+    Ok(match expr {
+        Expr::Integer(i) => ConstValue::Int(i128::try_from(*i).map_err(|_| EvalError::Overflow)?),
+        Expr::Float(raw) => ConstValue::Float(raw.parse().map_err(|_| EvalError::NotConstant)?),
+        Expr::Bool(b) => ConstValue::Bool(*b),
+        Expr::String(s) => ConstValue::Str(s.clone()),
+        Expr::Group(inner) => eval_const(inner)?,
+        Expr::UnaryExpr { operator, value } => {
+            let operator = *operator.node();
+
+            match (operator, eval_const(value)?) {
+                (Operator::Add, value @ (ConstValue::Int(_) | ConstValue::Float(_))) => value,
+                (Operator::Sub, ConstValue::Int(i)) => {
+                    ConstValue::Int(i.checked_neg().ok_or(EvalError::Overflow)?)
+                }
+                (Operator::Sub, ConstValue::Float(f)) => ConstValue::Float(-f),
+                (Operator::Not, ConstValue::Bool(b)) => ConstValue::Bool(!b),
+                (Operator::BitNot, ConstValue::Int(i)) => ConstValue::Int(!i),
+                _ => return Err(EvalError::InvalidOperands(operator)),
+            }
+        }
+        Expr::BinaryExpr { operator, lhs, rhs } => {
+            let operator = *operator.node();
+            let lhs = eval_const(lhs)?;
+
+            match (operator, &lhs) {
+                (Operator::And, ConstValue::Bool(false)) => return Ok(lhs),
+                (Operator::Or, ConstValue::Bool(true)) => return Ok(lhs),
+                _ => (),
+            }
+            eval_binary(operator, lhs, eval_const(rhs)?)?
+        }
+        _ => return Err(EvalError::NotConstant),
+    })
+}
+
+#[allow(clippy::cast_precision_loss)] // Mixing integers and floats is lossy at runtime too
+fn eval_binary(
+    operator: Operator,
+    lhs: ConstValue,
+    rhs: ConstValue,
+) -> Result<ConstValue, EvalError> {
+    let invalid = EvalError::InvalidOperands(operator);
+
+    Ok(match (lhs, rhs) {
+        (ConstValue::Int(a), ConstValue::Int(b)) => {
+            let shift = || u32::try_from(b).map_err(|_| EvalError::Overflow);
+            let int = |result: Option<i128>| result.map(ConstValue::Int).ok_or(EvalError::Overflow);
+
+            if b == 0 && matches!(operator, Operator::Div | Operator::FloorDiv | Operator::Mod) {
+                return Err(EvalError::DivisionByZero);
+            }
+            match operator {
+                Operator::Add => int(a.checked_add(b))?,
+                Operator::Sub => int(a.checked_sub(b))?,
+                Operator::Mul => int(a.checked_mul(b))?,
+                Operator::Div => int(a.checked_div(b))?,
+                Operator::FloorDiv => {
+                    let quotient = a.checked_div(b).ok_or(EvalError::Overflow)?;
+                    // Division truncates towards zero, so round down when the signs differ
+                    ConstValue::Int(if a % b != 0 && (a < 0) != (b < 0) {
+                        quotient - 1
+                    } else {
+                        quotient
+                    })
+                }
+                Operator::Mod => int(a.checked_rem(b))?,
+                Operator::Pow => {
+                    let exp = u32::try_from(b).map_err(|_| invalid)?;
+                    int(a.checked_pow(exp))?
+                }
+                Operator::BitOr => ConstValue::Int(a | b),
+                Operator::BitXor => ConstValue::Int(a ^ b),
+                Operator::BitAnd => ConstValue::Int(a & b),
+                Operator::BitLShift => {
+                    let shift = shift()?;
+                    // `checked_shl` only checks the shift amount, not the bits shifted out
+                    int(a.checked_shl(shift).filter(|&result| result >> shift == a))?
+                }
+                Operator::BitRShift => int(a.checked_shr(shift()?))?,
+                _ => compare(operator, &a, &b).ok_or(invalid)?,
+            }
+        }
+        (ConstValue::Int(a), ConstValue::Float(b)) => eval_float(operator, a as f64, b)?,
+        (ConstValue::Float(a), ConstValue::Int(b)) => eval_float(operator, a, b as f64)?,
+        (ConstValue::Float(a), ConstValue::Float(b)) => eval_float(operator, a, b)?,
+        (ConstValue::Bool(a), ConstValue::Bool(b)) => match operator {
+            Operator::And | Operator::Or => ConstValue::Bool(b),
+            Operator::Eq => ConstValue::Bool(a == b),
+            Operator::Ne => ConstValue::Bool(a != b),
+            _ => return Err(invalid),
+        },
+        (ConstValue::Str(a), ConstValue::Str(b)) => match operator {
+            Operator::Add => ConstValue::Str(a + &b),
+            _ => compare(operator, &a, &b).ok_or(invalid)?,
+        },
+        _ => return Err(invalid),
+    })
+}
+
+fn eval_float(operator: Operator, a: f64, b: f64) -> Result<ConstValue, EvalError> {
+    if b == 0.0 && matches!(operator, Operator::Div | Operator::FloorDiv | Operator::Mod) {
+        return Err(EvalError::DivisionByZero);
+    }
+
+    Ok(ConstValue::Float(match operator {
+        Operator::Add => a + b,
+        Operator::Sub => a - b,
+        Operator::Mul => a * b,
+        Operator::Div => a / b,
+        Operator::FloorDiv => (a / b).floor(),
+        Operator::Mod => a % b,
+        Operator::Pow => a.powf(b),
+        _ => return compare(operator, &a, &b).ok_or(EvalError::InvalidOperands(operator)),
+    }))
+}
+
+fn compare<T: PartialOrd>(operator: Operator, a: &T, b: &T) -> Option<ConstValue> {
+    Some(ConstValue::Bool(match operator {
+        Operator::Eq => a == b,
+        Operator::Ne => a != b,
+        Operator::Lt => a < b,
+        Operator::Le => a <= b,
+        Operator::Gt => a > b,
+        Operator::Ge => a >= b,
+        _ => return None,
+    }))
+}
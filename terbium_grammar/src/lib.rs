@@ -1,22 +1,33 @@
+// TODO: this crate could be `no_std` with `alloc` if it weren't for chumsky, which requires
+// std as of 0.8. Until then, the `std` feature only gates ariadne and the I/O APIs.
 #![feature(trait_alias)]
 
 pub mod ast;
+pub mod cst;
+pub mod cursor;
 pub mod error;
+pub mod fold;
+pub mod lint;
+pub mod registry;
+pub mod sourcemap;
 pub mod token;
+pub mod util;
 
 pub use crate::ast::{Body, Expr, Node, ParseInterface, TypeExpr};
 pub use crate::error::*;
-pub use crate::token::{get_lexer as tokenizer, Operator, Token};
+pub use crate::token::{get_lexer as tokenizer, LexerConfig, Operator, SlashSlash, Token};
 pub use chumsky::Parser as ChumskyParser;
 pub use chumsky::Stream as ChumskyStream;
 
-use std::{
+use core::{
     cmp::{Eq, PartialEq},
     fmt::{Debug, Display, Formatter, Result as FmtResult},
     ops::{Deref, DerefMut, Range},
-    path::{Path, PathBuf},
 };
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
 
+#[cfg(feature = "std")]
 use ariadne::Span as AriadneSpan;
 use chumsky::Span as ChumskySpan;
 
@@ -29,7 +40,15 @@ impl Source {
         Self(vec!["<repl>".to_string()])
     }
 
+    /// The source of code which was generated rather than written, see
+    /// [`TokenBuilder`](crate::token::TokenBuilder).
     #[must_use]
+    pub fn generated() -> Self {
+        Self(vec!["<generated>".to_string()])
+    }
+
+    #[must_use]
+    #[cfg(feature = "std")]
     pub fn from_path<P>(path: P) -> Self
     where
         P: AsRef<Path>,
@@ -43,6 +62,7 @@ impl Source {
     }
 
     #[must_use]
+    #[cfg(feature = "std")]
     pub fn to_path(&self) -> PathBuf {
         self.0.iter().map(ToString::to_string).collect()
     }
@@ -129,6 +149,58 @@ impl Span {
     pub const fn end(&self) -> usize {
         self.range.1
     }
+
+    /// The number of chars the span covers. Spans hold char offsets, see
+    /// [`Span::byte_len`] for the length in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.range.1.saturating_sub(self.range.0)
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the span lies entirely within `range`.
+    #[must_use]
+    pub const fn is_within(&self, range: &Range<usize>) -> bool {
+        range.start <= self.range.0 && self.range.1 <= range.end
+    }
+
+    /// Whether the span and `range` share at least one offset.
+    #[must_use]
+    pub const fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.range.0 < range.end && range.start < self.range.1
+    }
+
+    /// Converts the char offsets of this span into byte offsets within `src`, the source
+    /// it was produced from. Offsets past the end of `src` are clamped to its length.
+    #[must_use]
+    pub fn to_byte_range(&self, src: &str) -> Range<usize> {
+        self.convert_range(src, char::len_utf8)
+    }
+
+    /// Converts the char offsets of this span into UTF-16 code unit offsets within `src`,
+    /// the source it was produced from, as used by e.g. JavaScript and the Language Server
+    /// Protocol. Offsets past the end of `src` are clamped to its length.
+    #[must_use]
+    pub fn to_utf16_range(&self, src: &str) -> Range<usize> {
+        self.convert_range(src, char::len_utf16)
+    }
+
+    /// The number of bytes the span covers within `src`, the source it was produced from.
+    #[must_use]
+    pub fn byte_len(&self, src: &str) -> usize {
+        let range = self.to_byte_range(src);
+        range.end - range.start
+    }
+
+    fn convert_range(&self, src: &str, len: fn(char) -> usize) -> Range<usize> {
+        let offset = |chars: usize| src.chars().take(chars).map(len).sum();
+
+        offset(self.range.0)..offset(self.range.1)
+    }
 }
 
 impl Default for Span {
@@ -167,6 +239,7 @@ impl ChumskySpan for Span {
     }
 }
 
+#[cfg(feature = "std")]
 impl AriadneSpan for Span {
     type SourceId = Source;
 
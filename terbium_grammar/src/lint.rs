@@ -0,0 +1,248 @@
+use super::ast::{Body, Expr, Node, SpannedExpr, SpannedNode};
+use super::token::Associativity;
+use super::{Error, Operator, Severity};
+
+/// How tightly an expression binds, following the layers of the expression parser from the
+/// loosest (ranges) to the tightest (atoms and attributes).
+const fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::Range { .. } => 0,
+        Expr::BinaryExpr { operator, .. } => match operator.node() {
+            Operator::BitAnd
+            | Operator::BitOr
+            | Operator::BitXor
+            | Operator::BitLShift
+            | Operator::BitRShift => 1,
+            Operator::Or => 2,
+            Operator::And => 3,
+            Operator::Add | Operator::Sub => 5,
+            Operator::Mul | Operator::Div | Operator::FloorDiv | Operator::Mod => 6,
+            Operator::Pow => 7,
+            // Comparisons, which are the only operators left
+            _ => 4,
+        },
+        Expr::Cast(..) => 8,
+        Expr::UnaryExpr { .. } => 9,
+        Expr::Call { .. } => 10,
+        _ => 11,
+    }
+}
+
+/// Finds parentheses which could be removed without changing how the code parses, e.g.
+/// both pairs in `((a))` or the ones in `(a) + b`, and reports each of them as a warning
+/// spanning the parentheses.
+#[must_use]
+pub fn redundant_parens(body: &Body) -> Vec<Error> {
+    let mut warnings = Vec::new();
+    visit_nodes(&body.0, &mut warnings);
+
+    warnings.sort_by_key(|w| w.span.start());
+    warnings
+}
+
+/// Finds comparisons whose left-hand side is another comparison, e.g. `a < b < c`, which
+/// compares the result of `a < b` with `c`. Each chain is reported once with the given
+/// severity, spanning the whole chain. Parenthesizing the inner comparison, as in
+/// `(a < b) == c`, marks it as intended.
+#[must_use]
+pub fn chained_comparisons(body: &Body, severity: Severity) -> Vec<Error> {
+    let mut errors = Vec::new();
+    walk_nodes(&body.0, &mut |expr| {
+        if let Expr::BinaryExpr { operator, lhs, .. } = expr.node() {
+            if let Expr::BinaryExpr {
+                operator: inner, ..
+            } = lhs.node()
+            {
+                if is_comparison(*operator.node()) && is_comparison(*inner.node()) {
+                    errors.push(Error {
+                        severity,
+                        ..Error::chained_comparison(expr.span(), *inner.node(), *operator.node())
+                    });
+                }
+            }
+        }
+    });
+
+    // The rest of a chain, e.g. `a < b` in `a < b < c`, is visited after it and starts at
+    // the same offset
+    errors.sort_by_key(|e| e.span.start());
+    errors.dedup_by_key(|e| e.span.start());
+    errors
+}
+
+const fn is_comparison(operator: Operator) -> bool {
+    matches!(
+        operator,
+        Operator::Eq | Operator::Ne | Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge
+    )
+}
+
+/// Calls `f` with every expression within `nodes`, outer expressions before inner ones.
+fn walk_nodes(nodes: &[SpannedNode], f: &mut impl FnMut(&SpannedExpr)) {
+    for node in nodes {
+        match node.node() {
+            Node::Module(nodes) => walk_nodes(nodes, f),
+            Node::Func { params, body, .. } => {
+                for param in params {
+                    if let Some(default) = &param.default {
+                        walk(default, f);
+                    }
+                }
+                walk_nodes(body, f);
+            }
+            Node::Expr(value)
+            | Node::Declare { value, .. }
+            | Node::Assign { value, .. }
+            | Node::Return(Some(value)) => walk(value, f),
+            Node::Return(None) | Node::Require(_) => (),
+        }
+    }
+}
+
+fn walk(expr: &SpannedExpr, f: &mut impl FnMut(&SpannedExpr)) {
+    f(expr);
+
+    match expr.node() {
+        Expr::Group(inner) | Expr::UnaryExpr { value: inner, .. } | Expr::Attr(inner, _) => {
+            walk(inner, f);
+        }
+        Expr::Array(items) => items.iter().for_each(|item| walk(item, f)),
+        Expr::Cast(lhs, rhs) | Expr::BinaryExpr { lhs, rhs, .. } => {
+            walk(lhs, f);
+            walk(rhs, f);
+        }
+        Expr::Call {
+            value,
+            args,
+            kwargs,
+        } => {
+            walk(value, f);
+            for arg in args.iter().chain(kwargs.iter().map(|(_, arg)| arg)) {
+                walk(arg, f);
+            }
+        }
+        Expr::If {
+            condition,
+            body,
+            else_if_bodies,
+            else_body,
+        } => {
+            walk(condition, f);
+            walk_nodes(&body.node().0, f);
+
+            for (condition, body) in else_if_bodies {
+                walk(condition, f);
+                walk_nodes(&body.node().0, f);
+            }
+            if let Some(body) = else_body {
+                walk_nodes(&body.node().0, f);
+            }
+        }
+        Expr::While { condition, body } => {
+            walk(condition, f);
+            walk_nodes(body, f);
+        }
+        Expr::Range { start, end } => start.iter().chain(end).for_each(|bound| walk(bound, f)),
+        Expr::Integer(_) | Expr::Float(_) | Expr::String(_) | Expr::Bool(_) | Expr::Ident(_) => {}
+    }
+}
+
+fn visit_nodes(nodes: &[SpannedNode], warnings: &mut Vec<Error>) {
+    for node in nodes {
+        match node.node() {
+            Node::Module(nodes) => visit_nodes(nodes, warnings),
+            Node::Func { params, body, .. } => {
+                for param in params {
+                    if let Some(default) = &param.default {
+                        visit(default, None, warnings);
+                    }
+                }
+                visit_nodes(body, warnings);
+            }
+            Node::Expr(value)
+            | Node::Declare { value, .. }
+            | Node::Assign { value, .. }
+            | Node::Return(Some(value)) => visit(value, None, warnings),
+            Node::Return(None) | Node::Require(_) => (),
+        }
+    }
+}
+
+/// Visits `expr`, which may only be unparenthesized if it binds at least as tightly as
+/// `min_precedence`, or anywhere if there is no minimum.
+fn visit(expr: &SpannedExpr, min_precedence: Option<u8>, warnings: &mut Vec<Error>) {
+    match expr.node() {
+        Expr::Group(inner) => {
+            if min_precedence.is_none_or(|min| precedence(inner.node()) >= min) {
+                warnings.push(Error::redundant_parens(expr.span()));
+            }
+            visit(inner, None, warnings);
+        }
+        Expr::Array(items) => {
+            for item in items {
+                visit(item, None, warnings);
+            }
+        }
+        Expr::Cast(subject, ty) => {
+            visit(subject, Some(8), warnings);
+            visit(ty, Some(9), warnings);
+        }
+        // Unary operators can be repeated, but removing the parentheses in `&(&a)` would
+        // turn the two `&` into `&&`
+        Expr::UnaryExpr { value, .. } => visit(value, Some(10), warnings),
+        Expr::BinaryExpr { operator, lhs, rhs } => {
+            let p = precedence(expr.node());
+            let (lhs_min, rhs_min) = if operator.associativity() == Associativity::Right {
+                (p + 1, p)
+            } else if is_comparison(*operator.node()) {
+                // The parentheses in `(a < b) == c` mark the chain as intended, see
+                // `chained_comparisons`
+                (p + 1, p + 1)
+            } else {
+                (p, p + 1)
+            };
+
+            visit(lhs, Some(lhs_min), warnings);
+            visit(rhs, Some(rhs_min), warnings);
+        }
+        Expr::Attr(value, _) => visit(value, Some(11), warnings),
+        Expr::Call {
+            value,
+            args,
+            kwargs,
+        } => {
+            visit(value, Some(11), warnings);
+            for arg in args.iter().chain(kwargs.iter().map(|(_, arg)| arg)) {
+                visit(arg, None, warnings);
+            }
+        }
+        Expr::If {
+            condition,
+            body,
+            else_if_bodies,
+            else_body,
+        } => {
+            visit(condition, None, warnings);
+            visit_nodes(&body.node().0, warnings);
+
+            for (condition, body) in else_if_bodies {
+                visit(condition, None, warnings);
+                visit_nodes(&body.node().0, warnings);
+            }
+            if let Some(body) = else_body {
+                visit_nodes(&body.node().0, warnings);
+            }
+        }
+        Expr::While { condition, body } => {
+            visit(condition, None, warnings);
+            visit_nodes(body, warnings);
+        }
+        // Ranges don't chain, so `(a..b)..c` needs its parentheses on either side
+        Expr::Range { start, end } => {
+            for bound in start.iter().chain(end) {
+                visit(bound, Some(1), warnings);
+            }
+        }
+        Expr::Integer(_) | Expr::Float(_) | Expr::String(_) | Expr::Bool(_) | Expr::Ident(_) => {}
+    }
+}
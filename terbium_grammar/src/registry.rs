@@ -0,0 +1,104 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::ops::Range;
+
+/// Identifies a file within a [`SourceRegistry`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(usize);
+
+/// A range of character offsets within a file of a [`SourceRegistry`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourcedSpan {
+    pub file: FileId,
+    pub range: Range<usize>,
+}
+
+impl SourcedSpan {
+    #[must_use]
+    pub const fn new(file: FileId, range: Range<usize>) -> Self {
+        Self { file, range }
+    }
+}
+
+/// A human-readable position within a file. Lines and columns are one-based,
+/// and columns count characters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Location<'a> {
+    pub file: &'a str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Display for Location<'_> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct File {
+    name: String,
+    len: usize,
+    line_starts: Vec<usize>,
+}
+
+/// Holds every file of a project so that spans within any of them can be resolved to
+/// `file:line:col`.
+#[derive(Clone, Debug, Default)]
+pub struct SourceRegistry {
+    files: Vec<File>,
+}
+
+impl SourceRegistry {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { files: Vec::new() }
+    }
+
+    /// Registers a file along with its contents.
+    pub fn add(&mut self, name: impl Into<String>, text: &str) -> FileId {
+        self.files.push(File {
+            name: name.into(),
+            len: text.chars().count(),
+            line_starts: line_starts(text),
+        });
+
+        FileId(self.files.len() - 1)
+    }
+
+    /// The name the given file was registered with.
+    #[must_use]
+    pub fn name(&self, file: FileId) -> Option<&str> {
+        self.files.get(file.0).map(|file| file.name.as_str())
+    }
+
+    /// Resolves the start of a span to a line and column, or `None` if its file isn't part
+    /// of this registry or it starts past the end of its file.
+    #[must_use]
+    pub fn resolve(&self, span: &SourcedSpan) -> Option<Location<'_>> {
+        let file = self.files.get(span.file.0)?;
+        let offset = span.range.start;
+
+        if offset > file.len {
+            return None;
+        }
+        let line = file.line_starts.partition_point(|&start| start <= offset) - 1;
+
+        Some(Location {
+            file: &file.name,
+            line: line + 1,
+            column: offset - file.line_starts[line] + 1,
+        })
+    }
+}
+
+/// The character offsets at which each line of `text` starts.
+pub(crate) fn line_starts(text: &str) -> Vec<usize> {
+    core::iter::once(0)
+        .chain(
+            text.chars()
+                .enumerate()
+                .filter(|(_, c)| *c == '\n')
+                .map(|(i, _)| i + 1),
+        )
+        .collect()
+}
@@ -0,0 +1,267 @@
+use super::registry::line_starts;
+use super::{Source, Span};
+
+use core::fmt::Write;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The char and byte offsets at which a line starts.
+type LineStart = (usize, usize);
+
+/// Maps a position in generated output back to a position in Terbium source code.
+///
+/// Lines and columns are zero-based. Columns count UTF-16 code units as the source map
+/// format requires, unlike [`Span`] offsets, which count characters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Mapping {
+    pub generated_line: usize,
+    pub generated_column: usize,
+    /// The index of the original source, in the order they were added to the builder.
+    pub source: usize,
+    pub original_line: usize,
+    pub original_column: usize,
+}
+
+/// Builds a [Source Map v3](https://sourcemaps.info/spec.html) for code generated from
+/// Terbium source, e.g. when transpiling it to JavaScript.
+///
+/// Add every original source with [`SourceMapBuilder::add_source`], then record the span
+/// that each piece of output was generated from with [`SourceMapBuilder::add_mapping`].
+#[derive(Clone, Debug, Default)]
+pub struct SourceMapBuilder {
+    file: String,
+    /// Each source along with its text and the offsets its lines start at.
+    sources: Vec<(Source, String, Vec<LineStart>)>,
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMapBuilder {
+    /// Creates a builder for a source map of the given generated file.
+    #[must_use]
+    pub fn new(file: impl Into<String>) -> Self {
+        Self {
+            file: file.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Adds an original source which spans passed to [`SourceMapBuilder::add_mapping`]
+    /// can refer to, returning its index. Adding the same source twice returns the index
+    /// it already has.
+    pub fn add_source(&mut self, source: Source, text: &str) -> usize {
+        if let Some(index) = self.sources.iter().position(|(s, ..)| s == &source) {
+            return index;
+        }
+
+        let byte_starts = core::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1));
+        self.sources.push((
+            source,
+            text.to_string(),
+            line_starts(text).into_iter().zip(byte_starts).collect(),
+        ));
+        self.sources.len() - 1
+    }
+
+    /// Records that the output at the given generated position was generated from the code
+    /// starting at `span`. `generated_column` counts UTF-16 code units.
+    ///
+    /// # Panics
+    /// * The source of `span` was not added through [`SourceMapBuilder::add_source`].
+    pub fn add_mapping(&mut self, generated_line: usize, generated_column: usize, span: &Span) {
+        let source = self
+            .sources
+            .iter()
+            .position(|(s, ..)| s == &span.source)
+            .expect("source of span was not added to the source map");
+
+        let (_, text, line_starts) = &self.sources[source];
+        let original_line = line_starts.partition_point(|&(start, _)| start <= span.start()) - 1;
+        let (char_start, byte_start) = line_starts[original_line];
+
+        self.mappings.push(Mapping {
+            generated_line,
+            generated_column,
+            source,
+            original_line,
+            original_column: text[byte_start..]
+                .chars()
+                .take(span.start() - char_start)
+                .map(char::len_utf16)
+                .sum(),
+        });
+    }
+
+    /// The recorded mappings, in the order they were added.
+    #[must_use]
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// Encodes the recorded mappings into the `mappings` field of a source map, i.e.
+    /// base64 VLQ segments separated by `,` within a line and `;` between lines.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)] // Positions never come close to i64::MAX
+    pub fn encode_mappings(&self) -> String {
+        let mut mappings = self.mappings.clone();
+        mappings.sort_by_key(|m| (m.generated_line, m.generated_column));
+
+        let mut out = String::new();
+        let mut line = 0;
+        // The generated column is relative to the previous segment on the same line,
+        // while every other field is relative to the previous segment overall.
+        let mut column = 0;
+        let mut prev = (0, 0, 0);
+
+        for (i, m) in mappings.iter().enumerate() {
+            if m.generated_line > line {
+                out.extend(core::iter::repeat_n(';', m.generated_line - line));
+                line = m.generated_line;
+                column = 0;
+            } else if i > 0 {
+                out.push(',');
+            }
+
+            for (value, last) in [
+                (m.generated_column, &mut column),
+                (m.source, &mut prev.0),
+                (m.original_line, &mut prev.1),
+                (m.original_column, &mut prev.2),
+            ] {
+                encode_vlq(&mut out, value as i64 - *last as i64);
+                *last = value;
+            }
+        }
+
+        out
+    }
+
+    /// Renders the source map as JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let strings = |strings: &mut dyn Iterator<Item = String>| {
+            strings
+                .map(|s| escape_json(&s))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        format!(
+            r#"{{"version":3,"file":{},"sources":[{}],"sourcesContent":[{}],"names":[],"mappings":"{}"}}"#,
+            escape_json(&self.file),
+            strings(&mut self.sources.iter().map(|(source, ..)| source.to_string())),
+            strings(&mut self.sources.iter().map(|(_, text, _)| text.clone())),
+            self.encode_mappings(),
+        )
+    }
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+    // The sign is stored in the lowest bit
+    let mut value = if value < 0 {
+        (value.unsigned_abs() << 1) | 1
+    } else {
+        value.unsigned_abs() << 1
+    };
+
+    loop {
+        let mut digit = (value & 0b1_1111) as usize;
+        value >>= 5;
+
+        if value > 0 {
+            digit |= 0b10_0000;
+        }
+        out.push(BASE64[digit] as char);
+
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+fn decode_vlq(chars: &mut impl Iterator<Item = char>) -> Option<i64> {
+    let mut value = 0_u64;
+    let mut shift = 0;
+
+    loop {
+        let c = chars.next()?;
+        let digit = BASE64.iter().position(|&b| char::from(b) == c)? as u64;
+        value |= (digit & 0b1_1111).checked_shl(shift)?;
+        shift += 5;
+
+        if digit & 0b10_0000 == 0 {
+            break;
+        }
+    }
+
+    let magnitude = i64::try_from(value >> 1).ok()?;
+    Some(if value & 1 == 1 {
+        -magnitude
+    } else {
+        magnitude
+    })
+}
+
+/// Decodes the `mappings` field of a source map, as produced by
+/// [`SourceMapBuilder::encode_mappings`]. Segments which don't refer to a source are
+/// skipped. Returns `None` if the field is malformed.
+#[must_use]
+pub fn decode_mappings(mappings: &str) -> Option<Vec<Mapping>> {
+    let mut decoded = Vec::new();
+    let mut prev = [0_i64; 4];
+
+    for (generated_line, line) in mappings.split(';').enumerate() {
+        prev[0] = 0;
+
+        for segment in line.split(',').filter(|s| !s.is_empty()) {
+            let mut chars = segment.chars().peekable();
+            let mut fields = Vec::with_capacity(5);
+
+            while chars.peek().is_some() {
+                fields.push(decode_vlq(&mut chars)?);
+            }
+            if !matches!(fields.len(), 1 | 4 | 5) {
+                return None;
+            }
+
+            for (prev, delta) in prev.iter_mut().zip(&fields) {
+                *prev += delta;
+            }
+            if fields.len() == 1 {
+                continue;
+            }
+
+            let [generated_column, source, original_line, original_column] = prev;
+            decoded.push(Mapping {
+                generated_line,
+                generated_column: usize::try_from(generated_column).ok()?,
+                source: usize::try_from(source).ok()?,
+                original_line: usize::try_from(original_line).ok()?,
+                original_column: usize::try_from(original_column).ok()?,
+            });
+        }
+    }
+
+    Some(decoded)
+}
+
+fn escape_json(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
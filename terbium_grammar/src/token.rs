@@ -2,15 +2,34 @@ use super::{Error, Span};
 
 use chumsky::prelude::*;
 
-use std::{fmt::Display, hash::Hash};
+use core::{
+    cell::Cell,
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    mem::Discriminant,
+    num::ParseFloatError,
+    ops::Index,
+    slice::SliceIndex,
+    str::FromStr,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+#[cfg(feature = "std")]
+use std::{
+    collections::VecDeque,
+    io::{ErrorKind, Read},
+};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Operator {
     // Arithmetic
     Add,
     Sub,
     Mul,
     Div,
+    /// `//`, which is only lexed with [`SlashSlash::FloorDiv`].
+    FloorDiv,
     Mod,
     Pow,
     // Logical
@@ -32,13 +51,88 @@ pub enum Operator {
     BitRShift,
     // Programmatic
     Range,
+    /// `&mut`, which the lexer never emits on its own. The parser produces it when `&` is
+    /// immediately followed by `mut` in prefix position; anywhere else the two stay separate.
+    RefMut,
+}
+
+/// The positions an operator may be used in, see [`Operator::arity`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Arity {
+    /// Prefix position only, e.g. `~`.
+    Unary,
+    /// Infix position only, e.g. `*`.
+    Binary,
+    /// Both prefix and infix position, e.g. `-`.
+    Both,
+}
+
+/// How a chain of the same binary operator groups, see [`Operator::associativity`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`.
+    Left,
+    /// `a ** b ** c` is `a ** (b ** c)`.
+    Right,
+    /// The operator can't be chained, e.g. `a..b..c` is an error.
+    NonAssociative,
 }
 
 impl Operator {
+    /// How a chain of this operator groups when used infix. Operators which can only be used
+    /// in prefix position are [`Associativity::Left`].
+    #[must_use]
+    pub const fn associativity(&self) -> Associativity {
+        match self {
+            Self::Pow => Associativity::Right,
+            Self::Range => Associativity::NonAssociative,
+            _ => Associativity::Left,
+        }
+    }
+
+    /// Whether this operator may be used in prefix position, infix position or both. This
+    /// agrees with [`Operator::supports_unary`] and [`Operator::supports_binary`].
+    #[must_use]
+    pub const fn arity(&self) -> Arity {
+        match (self.supports_unary(), self.supports_binary()) {
+            (true, true) => Arity::Both,
+            (true, false) => Arity::Unary,
+            // Every operator supports at least one of the two
+            (false, _) => Arity::Binary,
+        }
+    }
+
+    /// Whether this operator may be used in prefix position.
+    ///
+    /// Note that `&` is the address-of operator when used as a prefix (`&x`) but the bitwise
+    /// and operator when used infix (`a & b`). The token is the same, so the parser tells them
+    /// apart solely by position.
     #[must_use]
     pub const fn supports_unary(&self) -> bool {
-        // TODO: &ident could retrieve memory address of the object
-        matches!(self, Self::Add | Self::Sub | Self::Not | Self::BitNot)
+        matches!(
+            self,
+            Self::Add | Self::Sub | Self::Not | Self::BitNot | Self::BitAnd | Self::RefMut
+        )
+    }
+
+    /// Whether this operator can be combined with `=` into an augmented assignment, i.e. `+=`.
+    #[must_use]
+    pub const fn supports_assignment(&self) -> bool {
+        matches!(
+            self,
+            Self::Add
+                | Self::Sub
+                | Self::Mul
+                | Self::Div
+                | Self::FloorDiv
+                | Self::Mod
+                | Self::Pow
+                | Self::BitOr
+                | Self::BitXor
+                | Self::BitAnd
+                | Self::BitLShift
+                | Self::BitRShift
+        )
     }
 
     #[must_use]
@@ -49,6 +143,7 @@ impl Operator {
                 | Self::Sub
                 | Self::Mul
                 | Self::Div
+                | Self::FloorDiv
                 | Self::Mod
                 | Self::Pow
                 | Self::Eq
@@ -68,15 +163,56 @@ impl Operator {
                 | Self::Range
         )
     }
+
+    /// The comparison operator which holds exactly when this one doesn't, i.e. `<` for `>=`,
+    /// so that `!(a < b)` can be rewritten as `a >= b`. Other operators have no such negation.
+    #[must_use]
+    pub const fn negate(&self) -> Option<Self> {
+        Some(match self {
+            Self::Eq => Self::Ne,
+            Self::Ne => Self::Eq,
+            Self::Lt => Self::Ge,
+            Self::Le => Self::Gt,
+            Self::Gt => Self::Le,
+            Self::Ge => Self::Lt,
+            _ => return None,
+        })
+    }
+
+    /// The operator which gives the same result once its operands are swapped, i.e. `>` for
+    /// `<`, so that `a < b` can be rewritten as `b > a`. Operators such as `-` have none.
+    ///
+    /// `+` and `*` are not flippable since they aren't commutative for strings, and neither
+    /// are `&&` and `||` since they short-circuit.
+    #[must_use]
+    pub const fn flip_operands(&self) -> Option<Self> {
+        Some(match self {
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+            Self::Eq | Self::Ne | Self::BitOr | Self::BitXor | Self::BitAnd => *self,
+            _ => return None,
+        })
+    }
 }
 
 impl Display for Operator {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        f.write_str(match self {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Operator {
+    /// The canonical spelling of this operator, which is also what it displays as.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
             Self::Add => "+",
             Self::Sub => "-",
             Self::Mul => "*",
             Self::Div => "/",
+            Self::FloorDiv => "//",
             Self::Mod => "%",
             Self::Pow => "**",
             Self::Eq => "==",
@@ -95,65 +231,345 @@ impl Display for Operator {
             Self::BitLShift => "<<",
             Self::BitRShift => ">>",
             Self::Range => "..",
-        })
+            Self::RefMut => "&mut",
+        }
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// The quote character a string literal was written with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QuoteStyle {
+    #[default]
+    Double,
+    Single,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum StringLiteral {
-    String(String),
+    String(String, QuoteStyle),
     ByteString(String),
     RawString(String),
     InterpolatedString(String),
 }
 
-impl Display for StringLiteral {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        f.write_str(
-            match self {
-                Self::String(s) => format!("{:?}", s),
-                Self::ByteString(s) => format!("~{:?}", s),
-                Self::RawString(s) => format!("r{:?}", s),
-                Self::InterpolatedString(s) => format!("${:?}", s),
+/// A piece of an interpolated string, see [`parse_interpolation`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InterpolationPart {
+    Literal(String),
+    /// A hole such as `{value:>8.2}`. The spec is everything after the colon, verbatim.
+    Hole {
+        tokens: Vec<(Token, Span)>,
+        spec: Option<String>,
+    },
+}
+
+/// Splits the template of a [`StringLiteral::InterpolatedString`] into its literal chunks and
+/// holes, lexing the expression in each hole. Spans are char offsets into the template.
+///
+/// # Errors
+/// * The expression in a hole does not match Terbium grammar.
+pub fn parse_interpolation(template: &str) -> Result<Vec<InterpolationPart>, Vec<Error>> {
+    let source = super::Source::default();
+    let chars = template.chars().collect::<Vec<_>>();
+
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                literal.push(chars[i]);
+                i += 2;
             }
-            .as_str(),
-        )
+            ('{', _) => {
+                if !literal.is_empty() {
+                    parts.push(InterpolationPart::Literal(std::mem::take(&mut literal)));
+                }
+
+                // The lexer guarantees that holes are balanced
+                let start = i + 1;
+                let mut depth = 0_usize;
+                let mut colon = None;
+                i = start;
+
+                while depth > 0 || chars[i] != '}' {
+                    match chars[i] {
+                        '{' | '(' | '[' => depth += 1,
+                        '}' | ')' | ']' => depth = depth.saturating_sub(1),
+                        // :: is a cast, not the start of a spec
+                        ':' if chars.get(i + 1) == Some(&':') => i += 1,
+                        ':' if depth == 0 && colon.is_none() => colon = Some(i),
+                        _ => (),
+                    }
+                    i += 1;
+                }
+
+                let end = colon.unwrap_or(i);
+                let tokens = get_lexer().parse(chumsky::Stream::<_, Span, _>::from_iter(
+                    Span::single(source.clone(), end),
+                    (start..end).map(|j| (chars[j], Span::single(source.clone(), j))),
+                ))?;
+
+                parts.push(InterpolationPart::Hole {
+                    tokens,
+                    spec: colon.map(|colon| chars[colon + 1..i].iter().collect()),
+                });
+                i += 1;
+            }
+            (c, _) => {
+                literal.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    if !literal.is_empty() {
+        parts.push(InterpolationPart::Literal(literal));
+    }
+
+    Ok(parts)
+}
+
+/// Escapes `c` so that it lexes back into itself within a string quoted by `quote`.
+fn escape_char(c: char, quote: char) -> String {
+    match c {
+        '\\' => "\\\\".to_string(),
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        '\x08' => "\\b".to_string(),
+        '\x0C' => "\\f".to_string(),
+        // Control characters all lie below U+00A0, so two hex digits are enough
+        c if c.is_control() => format!("\\x{:02X}", c as u32),
+        c if c == quote => format!("\\{}", c),
+        c => c.to_string(),
+    }
+}
+
+/// Escapes the literal chunks of an interpolation template, leaving its holes and doubled
+/// braces as they are.
+fn escape_template(template: &str) -> String {
+    let mut depth = 0_usize;
+
+    template
+        .chars()
+        .map(|c| match c {
+            '{' => {
+                depth += 1;
+                c.to_string()
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                c.to_string()
+            }
+            // `{{` opens and closes again straight away, so it is never mistaken for a hole
+            _ if depth > 0 => c.to_string(),
+            _ => escape_char(c, '"'),
+        })
+        .collect()
+}
+
+impl StringLiteral {
+    /// A raw string with the given contents, or `None` if they contain a double quote, which
+    /// raw strings have no way to escape.
+    #[must_use]
+    pub fn raw(s: &str) -> Option<Self> {
+        (!s.contains('"')).then(|| Self::RawString(s.to_string()))
+    }
+}
+
+impl Display for StringLiteral {
+    /// Renders the literal as source code which lexes back into the same literal.
+    ///
+    /// Raw strings are rendered verbatim, so one which contains a double quote, which the
+    /// lexer never produces, can not be rendered faithfully.
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let escape = |s: &str, quote| s.chars().map(|c| escape_char(c, quote)).collect::<String>();
+
+        match self {
+            Self::String(s, QuoteStyle::Double) => write!(f, "\"{}\"", escape(s, '"')),
+            Self::String(s, QuoteStyle::Single) => write!(f, "'{}'", escape(s, '\'')),
+            Self::ByteString(s) => write!(f, "~\"{}\"", escape(s, '"')),
+            Self::RawString(s) => write!(f, "r\"{}\"", s),
+            Self::InterpolatedString(s) => write!(f, "$\"{}\"", escape_template(s)),
+        }
+    }
+}
+
+/// A float literal, which keeps both its source text and its value.
+///
+/// Rust floats are not hashable, additionally we want to avoid as much floating point
+/// precision loss as possible, so equality, ordering and hashing only consider the source
+/// text. `1.0` and `1.00` are therefore different literals, see
+/// [`Literal::normalized_float`] for comparing them by value instead.
+#[derive(Clone, Debug)]
+pub struct Float {
+    /// A boxed `str` rather than a `String`, which keeps [`Token`] small, as the parser
+    /// keeps plenty of them on the stack for every nesting level.
+    pub raw: Box<str>,
+    pub value: f64,
+}
+
+// A `String` in `Float` would make every token 8 bytes larger
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(core::mem::size_of::<Token>() == 32);
+
+impl FromStr for Float {
+    type Err = ParseFloatError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            raw: raw.into(),
+            value: raw.parse()?,
+        })
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for Float {}
+
+impl PartialOrd for Float {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Float {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.raw.cmp(&other.raw)
+    }
+}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl Display for Float {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Literal {
     String(StringLiteral),
+    /// Integer literals are always decimal, since there are no radix prefixes such as `0x`,
+    /// so displaying one reproduces its source text.
     Integer(u128), // This can be unsigned since unary minus is parsed separate from Literal
-    Float(String), // Rust floats are not hashable, additionally we want to avoid as much floating point precision loss as possible
+    Float(Float),
+}
+
+impl Literal {
+    /// An integer literal, or `None` if `i` is too large for the lexer to produce, i.e. larger
+    /// than `i128::MAX`.
+    #[must_use]
+    pub fn int(i: u128) -> Option<Self> {
+        i128::try_from(i).is_ok().then_some(Self::Integer(i))
+    }
+
+    /// A float literal with the shortest text which lexes back into `value`, e.g. `1.0`, or
+    /// `inf` and `nan` which only lex as floats with [`LexerConfig::float_constants`].
+    /// Returns `None` for negative values, since a minus sign is never part of a literal.
+    #[must_use]
+    pub fn float_from_f64(value: f64) -> Option<Self> {
+        let raw = if value.is_nan() {
+            "nan".to_string()
+        } else if value.is_sign_negative() {
+            return None;
+        } else if value.is_infinite() {
+            "inf".to_string()
+        } else {
+            // Display never uses an exponent, which float literals don't support
+            let raw = value.to_string();
+
+            if raw.contains('.') {
+                raw
+            } else {
+                raw + ".0"
+            }
+        };
+
+        Some(Self::Float(Float {
+            raw: raw.into_boxed_str(),
+            value,
+        }))
+    }
+
+    /// A double quoted string literal. Any contents are allowed, since they are escaped
+    /// when displayed.
+    #[must_use]
+    pub fn string(s: &str) -> Self {
+        Self::String(StringLiteral::String(s.to_string(), QuoteStyle::Double))
+    }
+
+    /// The numeric value of this literal, or `None` if it is a string.
+    /// `inf` and `nan` float literals become the respective IEEE values.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::String(_) => None,
+            Self::Integer(i) => Some(*i as f64),
+            Self::Float(f) => Some(f.value),
+        }
+    }
+
+    /// The shortest text which parses back into the same value as this float literal,
+    /// so that e.g. `1.0`, `1.00` and `1.` all normalize to `1.0`. The literal itself keeps
+    /// its source text. Returns `None` if this is not a float literal.
+    #[must_use]
+    pub fn normalized_float(&self) -> Option<String> {
+        match self {
+            // Debug formatting of f64 is the shortest representation that round-trips
+            Self::Float(f) => Some(format!("{:?}", f.value)),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Literal {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.write_str(
             match self {
                 Self::String(s) => s.to_string(),
                 Self::Integer(i) => i.to_string(),
-                Self::Float(f) => f.clone(),
+                Self::Float(f) => f.raw.to_string(),
             }
             .as_str(),
         )
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Keyword {
     Func,
     Class,
+    Enum,
+    Struct,
+    Trait,
+    Impl,
+    Type,
     // Modules
     Require,
     Export,
+    From,
     // Variables
     Let,
     Const,
     Mut,
+    Static,
     // Attributes
     Private,
+    Pub,
+    Unsafe,
     // Control flow
     If,
     Else,
@@ -161,72 +577,203 @@ pub enum Keyword {
     For,
     In,
     While,
+    Loop,
+    Do,
     Break,
     Continue,
     Return,
+    Yield,
+    Defer,
     With,
     Throws,
+    Try,
+    Catch,
+    Finally,
     Where,
+    // Operators
+    As,
+    Is, // Binds as tightly as the comparison operators
+    // Asynchronous
+    Async,
+    Await,
+    // Paths
+    SelfKw,
+    Super,
 }
 
 impl Display for Keyword {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        f.write_str(match self {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Keyword {
+    /// The canonical spelling of this keyword, which is also what it displays as.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
             Self::Func => "func",
             Self::Class => "class",
+            Self::Enum => "enum",
+            Self::Struct => "struct",
+            Self::Trait => "trait",
+            Self::Impl => "impl",
+            Self::Type => "type",
             Self::Require => "require",
             Self::Export => "export",
+            Self::From => "from",
             Self::Let => "let",
             Self::Const => "const",
             Self::Mut => "mut",
+            Self::Static => "static",
             Self::Private => "private",
+            Self::Pub => "pub",
+            Self::Unsafe => "unsafe",
             Self::If => "if",
             Self::Else => "else",
             Self::Match => "match",
             Self::For => "for",
             Self::In => "in",
             Self::While => "while",
+            Self::Loop => "loop",
+            Self::Do => "do",
             Self::Break => "break",
             Self::Continue => "continue",
             Self::Return => "return",
+            Self::Yield => "yield",
+            Self::Defer => "defer",
             Self::With => "with",
             Self::Throws => "throws",
+            Self::Try => "try",
+            Self::Catch => "catch",
+            Self::Finally => "finally",
             Self::Where => "where",
-        })
+            Self::As => "as",
+            Self::Is => "is",
+            Self::Async => "async",
+            Self::Await => "await",
+            Self::SelfKw => "self",
+            Self::Super => "super",
+        }
     }
-}
 
-impl Keyword {
     #[must_use]
     pub const fn is_soft(&self) -> bool {
         !matches!(
             self,
             Self::Func
                 | Self::Class
+                | Self::Enum
+                | Self::Struct
+                | Self::Trait
+                | Self::Impl
                 | Self::Let
                 | Self::Const
                 | Self::Mut
+                | Self::Static
+                | Self::Unsafe
                 | Self::If
                 | Self::Else
                 | Self::For
                 | Self::In
                 | Self::While
+                | Self::Loop
+                | Self::Do
                 | Self::Break
                 | Self::Continue
                 | Self::Return
                 | Self::With
+                | Self::As
+                | Self::Async
+                | Self::Await
+                | Self::SelfKw
+                | Self::Super
+        )
+    }
+
+    /// The text of this keyword if it is soft, i.e. if the parser accepts it
+    /// wherever an identifier is expected, such as in `let where = 1;`.
+    #[must_use]
+    pub const fn as_identifier_text(&self) -> Option<&'static str> {
+        if self.is_soft() {
+            Some(self.as_str())
+        } else {
+            None
+        }
+    }
+
+    /// Whether this keyword introduces a declaration, such as `func` or `let`.
+    /// Modifiers which may precede one, such as `pub`, do not count.
+    #[must_use]
+    pub const fn starts_declaration(&self) -> bool {
+        matches!(
+            self,
+            Self::Func
+                | Self::Class
+                | Self::Enum
+                | Self::Struct
+                | Self::Trait
+                | Self::Impl
+                | Self::Type
+                | Self::Let
+                | Self::Const
+                | Self::Static
         )
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Bracket {
     Paren,   // ()
     Bracket, // []
     Brace,   // {}
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+impl Bracket {
+    /// The character which opens this kind of bracket, e.g. `(`.
+    #[must_use]
+    pub const fn open_char(self) -> char {
+        match self {
+            Self::Paren => '(',
+            Self::Bracket => '[',
+            Self::Brace => '{',
+        }
+    }
+
+    /// The character which closes this kind of bracket, e.g. `)`.
+    #[must_use]
+    pub const fn close_char(self) -> char {
+        match self {
+            Self::Paren => ')',
+            Self::Bracket => ']',
+            Self::Brace => '}',
+        }
+    }
+
+    /// Whether `close` closes `open`, i.e. whether they are the same kind of bracket.
+    #[must_use]
+    pub const fn matches(open: Self, close: Self) -> bool {
+        matches!(
+            (open, close),
+            (Self::Paren, Self::Paren)
+                | (Self::Bracket, Self::Bracket)
+                | (Self::Brace, Self::Brace)
+        )
+    }
+}
+
+/// Renders the opening character, see [`Bracket::open_char`].
+impl Display for Bracket {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}", self.open_char())
+    }
+}
+
+/// A single token of source code.
+///
+/// Tokens own all of their contents and never borrow from the source they were lexed from,
+/// so they are `'static` and can be kept after the source is gone.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Token {
     Invalid(char),
     Operator(Operator),
@@ -238,13 +785,81 @@ pub enum Token {
     Comma,
     Dot,
     Cast, // ::
+    Colon,
     Question,
+    At, // @, which starts a decorator such as `@cached` or `@route("/x")`
     Semicolon,
-    Assign, // =
+    Assign,                    // =
+    AugmentedAssign(Operator), // +=, <<=, etc.
+}
+
+impl Token {
+    /// The kind of this token regardless of its contents, e.g. every identifier has the
+    /// same kind, as does every operator.
+    #[must_use]
+    pub fn kind(&self) -> Discriminant<Self> {
+        core::mem::discriminant(self)
+    }
+
+    /// This token, as one which doesn't borrow from its source. Tokens already own their
+    /// contents, so this returns the token as is. It exists so that generic code can
+    /// explicitly detach tokens from the source before storing them.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // Tokens may borrow from the source in the future
+    pub fn into_owned(self) -> Self {
+        self
+    }
+
+    /// The length in bytes of this token's text, as it is displayed. This is also its
+    /// length in the source, except for literals which can be written in more than one way,
+    /// such as strings with escapes, and raw identifiers.
+    #[must_use]
+    pub fn byte_len(&self) -> usize {
+        match self {
+            Self::Identifier(s) => s.len(),
+            Self::Keyword(k) => k.as_str().len(),
+            Self::Operator(o) => o.as_str().len(),
+            Self::AugmentedAssign(o) => o.as_str().len() + 1,
+            Self::Invalid(c) => c.len_utf8(),
+            Self::Literal(_) => self.to_string().len(),
+            Self::StartBracket(b) => b.open_char().len_utf8(),
+            Self::EndBracket(b) => b.close_char().len_utf8(),
+            Self::Cast => 2,
+            Self::Comma
+            | Self::Dot
+            | Self::Colon
+            | Self::Question
+            | Self::At
+            | Self::Semicolon
+            | Self::Assign => 1,
+        }
+    }
+
+    /// The operator a compound assignment applies, e.g. `Add` for `+=` or `BitLShift` for
+    /// `<<=`, so that `a += b` can be desugared into `a = a + b`. This is `None` for every
+    /// other token, including a plain `=`.
+    #[must_use]
+    pub const fn compound_base(&self) -> Option<Operator> {
+        match self {
+            Self::AugmentedAssign(op) => Some(*op),
+            _ => None,
+        }
+    }
+
+    /// Describes this token if it is an assignment, so that all forms of assignment can be
+    /// handled alike: `Some(None)` for `=`, and `Some(Some(op))` for `op=`, e.g. `+=`.
+    #[must_use]
+    pub const fn as_assignment(&self) -> Option<Option<Operator>> {
+        match self {
+            Self::Assign => Some(None),
+            Self::AugmentedAssign(op) => Some(Some(*op)),
+            _ => None,
+        }
+    }
 }
 
 impl Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         let s: String;
 
         f.write_str(match self {
@@ -265,26 +880,161 @@ impl Display for Token {
                 s.as_str()
             }
             Self::Identifier(s) => s.as_str(),
-            Self::StartBracket(b) => match b {
-                Bracket::Paren => "(",
-                Bracket::Bracket => "[",
-                Bracket::Brace => "{",
-            },
-            Self::EndBracket(b) => match b {
-                Bracket::Paren => ")",
-                Bracket::Bracket => "]",
-                Bracket::Brace => "}",
-            },
+            Self::StartBracket(b) => {
+                s = b.open_char().to_string();
+                s.as_str()
+            }
+            Self::EndBracket(b) => {
+                s = b.close_char().to_string();
+                s.as_str()
+            }
             Self::Comma => ",",
             Self::Dot => ".",
             Self::Cast => "::",
+            Self::Colon => ":",
             Self::Question => "?",
+            Self::At => "@",
             Self::Semicolon => ";",
             Self::Assign => "=",
+            Self::AugmentedAssign(o) => {
+                s = format!("{}=", o);
+                s.as_str()
+            }
         })
     }
 }
 
+/// Whether `c` may start an identifier, exactly as the lexer decides it.
+#[must_use]
+pub const fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+/// Whether `c` may appear in an identifier after its first character, exactly as the lexer
+/// decides it.
+#[must_use]
+pub const fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Every keyword along with its spelling.
+pub static KEYWORDS: &[(&str, Keyword)] = &[
+    ("func", Keyword::Func),
+    ("class", Keyword::Class),
+    ("enum", Keyword::Enum),
+    ("struct", Keyword::Struct),
+    ("trait", Keyword::Trait),
+    ("impl", Keyword::Impl),
+    ("type", Keyword::Type),
+    ("require", Keyword::Require),
+    ("export", Keyword::Export),
+    ("from", Keyword::From),
+    ("let", Keyword::Let),
+    ("const", Keyword::Const),
+    ("mut", Keyword::Mut),
+    ("static", Keyword::Static),
+    ("private", Keyword::Private),
+    ("pub", Keyword::Pub),
+    ("unsafe", Keyword::Unsafe),
+    ("if", Keyword::If),
+    ("else", Keyword::Else),
+    ("match", Keyword::Match),
+    ("for", Keyword::For),
+    ("in", Keyword::In),
+    ("while", Keyword::While),
+    ("loop", Keyword::Loop),
+    ("do", Keyword::Do),
+    ("break", Keyword::Break),
+    ("continue", Keyword::Continue),
+    ("return", Keyword::Return),
+    ("yield", Keyword::Yield),
+    ("defer", Keyword::Defer),
+    ("with", Keyword::With),
+    ("throws", Keyword::Throws),
+    ("try", Keyword::Try),
+    ("catch", Keyword::Catch),
+    ("finally", Keyword::Finally),
+    ("where", Keyword::Where),
+    ("as", Keyword::As),
+    ("is", Keyword::Is),
+    ("async", Keyword::Async),
+    ("await", Keyword::Await),
+    ("self", Keyword::SelfKw),
+    ("super", Keyword::Super),
+];
+
+/// The canonical spelling of every token which is always spelled the same way, i.e. every
+/// keyword, operator, punctuation symbol and bracket, as opposed to identifiers and literals.
+#[must_use]
+pub fn fixed_token_spellings() -> &'static [(Token, &'static str)] {
+    static SPELLINGS: OnceLock<Vec<(Token, &str)>> = OnceLock::new();
+
+    SPELLINGS.get_or_init(|| {
+        KEYWORDS
+            .iter()
+            .map(|(text, keyword)| (Token::Keyword(keyword.clone()), *text))
+            .chain(SYMBOLS.iter().map(|(text, token)| (token.clone(), *text)))
+            .collect()
+    })
+}
+
+/// Every operator, punctuation symbol and bracket along with its text, longest first.
+///
+/// The lexer tries these in order, so keeping them sorted by length guarantees that the
+/// longest symbol always wins, i.e. `**=` never lexes as `**` followed by `=`. Symbols of
+/// equal length never share a prefix, so their order among each other does not matter.
+/// Runs of dots munch the same way: `a...b` lexes as `a`, `..`, `.`, `b`, since there is no
+/// three-dot symbol. Adding a symbol only takes a new entry here.
+pub static SYMBOLS: &[(&str, Token)] = &[
+    ("**=", Token::AugmentedAssign(Operator::Pow)),
+    ("<<=", Token::AugmentedAssign(Operator::BitLShift)),
+    (">>=", Token::AugmentedAssign(Operator::BitRShift)),
+    ("::", Token::Cast),
+    ("..", Token::Operator(Operator::Range)),
+    ("**", Token::Operator(Operator::Pow)),
+    ("==", Token::Operator(Operator::Eq)),
+    ("!=", Token::Operator(Operator::Ne)),
+    ("<=", Token::Operator(Operator::Le)),
+    (">=", Token::Operator(Operator::Ge)),
+    ("<<", Token::Operator(Operator::BitLShift)),
+    (">>", Token::Operator(Operator::BitRShift)),
+    ("||", Token::Operator(Operator::Or)),
+    ("&&", Token::Operator(Operator::And)),
+    ("+=", Token::AugmentedAssign(Operator::Add)),
+    ("-=", Token::AugmentedAssign(Operator::Sub)),
+    ("*=", Token::AugmentedAssign(Operator::Mul)),
+    ("/=", Token::AugmentedAssign(Operator::Div)),
+    ("%=", Token::AugmentedAssign(Operator::Mod)),
+    ("|=", Token::AugmentedAssign(Operator::BitOr)),
+    ("^=", Token::AugmentedAssign(Operator::BitXor)),
+    ("&=", Token::AugmentedAssign(Operator::BitAnd)),
+    (",", Token::Comma),
+    (";", Token::Semicolon),
+    ("?", Token::Question),
+    ("@", Token::At),
+    (":", Token::Colon),
+    (".", Token::Dot),
+    ("+", Token::Operator(Operator::Add)),
+    ("-", Token::Operator(Operator::Sub)),
+    ("*", Token::Operator(Operator::Mul)),
+    ("/", Token::Operator(Operator::Div)),
+    ("%", Token::Operator(Operator::Mod)),
+    ("!", Token::Operator(Operator::Not)),
+    ("=", Token::Assign),
+    ("<", Token::Operator(Operator::Lt)),
+    (">", Token::Operator(Operator::Gt)),
+    ("|", Token::Operator(Operator::BitOr)),
+    ("^", Token::Operator(Operator::BitXor)),
+    ("&", Token::Operator(Operator::BitAnd)),
+    ("~", Token::Operator(Operator::BitNot)),
+    ("(", Token::StartBracket(Bracket::Paren)),
+    ("[", Token::StartBracket(Bracket::Bracket)),
+    ("{", Token::StartBracket(Bracket::Brace)),
+    (")", Token::EndBracket(Bracket::Paren)),
+    ("]", Token::EndBracket(Bracket::Bracket)),
+    ("}", Token::EndBracket(Bracket::Brace)),
+];
+
 macro_rules! escape_hex {
     ($c:expr, $l:expr) => {{
         just($c).ignore_then(
@@ -305,150 +1055,1339 @@ macro_rules! escape_hex {
     }};
 }
 
+/// Options that tweak how source code is tokenized, for use with [`get_lexer_with_config`].
+///
+/// The default configuration tokenizes standard Terbium.
+#[derive(Clone, Debug, Default)]
+pub struct LexerConfig {
+    /// Whether keywords should be recognized regardless of their casing, i.e. `IF`, `If`
+    /// and `if` all becoming [`Keyword::If`]. Identifiers keep their original casing.
+    pub case_insensitive_keywords: bool,
+    /// Additional spellings for keywords, e.g. mapping `fn` to [`Keyword::Func`].
+    /// These are only consulted for identifiers that aren't already keywords.
+    pub keyword_aliases: HashMap<String, Keyword>,
+    /// Identifiers that are reserved for future use. They still lex as identifiers, and
+    /// [`lex_with_warnings`] reports each use with a warning.
+    pub reserved_identifiers: HashSet<String>,
+    /// Whether `inf` and `nan` are lexed as float literals rather than identifiers.
+    /// This has no effect without the `float` feature.
+    pub float_constants: bool,
+    /// The maximum length of an identifier in characters, to defend against pathological
+    /// inputs. Longer identifiers emit an error and are truncated to this length.
+    pub max_ident_len: Option<usize>,
+    /// The operators available in this dialect, e.g. to leave out bitwise operators when
+    /// embedding Terbium as a DSL. Other operators, including their augmented assignments,
+    /// emit an error instead of being tokenized. `None` allows every operator.
+    pub allowed_operators: Option<HashSet<Operator>>,
+    /// What `//` means, since it can be either a line comment or floor division.
+    pub slash_slash: SlashSlash,
+    /// The prefix of line comments, which is `//` by default. With [`SlashSlash::FloorDiv`]
+    /// there are no line comments unless this is set to something else, e.g. `#`.
+    pub line_comment_prefix: Option<String>,
+    /// Whether [`Lexer`] records if whitespace surrounds each operator, e.g. to tell `a -b`
+    /// from `a - b`. Tokens themselves never carry this, see [`Lexer::operator_spacing`].
+    pub record_operator_spacing: bool,
+    /// Whether [`Lexer`] records where each escape sequence within a string literal is, e.g.
+    /// to highlight `\n` differently from the rest of the string, see [`Lexer::escapes`].
+    pub record_escapes: bool,
+    /// What to do after running into a character which can't start a token.
+    pub recovery: RecoveryStrategy,
+}
+
+/// Whether whitespace directly precedes and follows an operator token.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperatorSpacing {
+    pub leading: bool,
+    pub trailing: bool,
+}
+
+/// The kind of an escape sequence within a string literal, see [`Lexer::escapes`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EscapeKind {
+    /// A backslash followed by a single character, e.g. `\n` or `\"`.
+    Char,
+    /// `\xNN`.
+    Hex,
+    /// `\uNNNN` or `\UNNNNNNNN`.
+    Unicode,
+    /// `%NN` or `%%` in a percent-encoded string.
+    Percent,
+    /// `{{` or `}}` in an interpolated string.
+    Brace,
+}
+
+/// How the lexer carries on after a character which can't start a token, see
+/// [`LexerConfig::recovery`]. Either way, an error is reported for it.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum RecoveryStrategy {
+    /// The character becomes a [`Token::Invalid`] and lexing resumes right after it, which
+    /// recovers as much as possible, e.g. for editors.
+    #[default]
+    SkipChar,
+    /// The character and everything up to the next whitespace become a single
+    /// [`Token::Invalid`] holding the character, so that e.g. `#foo` is one error rather
+    /// than `foo` being lexed as an identifier.
+    SkipToWhitespace,
+    /// Lexing stops at the character, e.g. for compilers which give up on the first error.
+    Abort,
+}
+
+/// What `//` is lexed as, see [`LexerConfig::slash_slash`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SlashSlash {
+    /// `//` starts a line comment.
+    #[default]
+    Comment,
+    /// `//` is the floor division operator, [`Operator::FloorDiv`], and `//=` its augmented
+    /// assignment.
+    FloorDiv,
+}
+
 #[must_use]
-#[allow(clippy::too_many_lines)]
-#[allow(clippy::cast_sign_loss)] // text::int does not handle signed
 pub fn get_lexer() -> impl Parser<char, Vec<(Token, Span)>, Error = Error> {
-    let integer = text::int::<_, Error>(10)
+    get_lexer_with_config(LexerConfig::default())
+}
+
+/// A lexer which can be reused across many calls, e.g. for every line in a REPL
+/// or every edit in a language server.
+///
+/// The parser is only built once and the buffer used to feed it characters is kept between
+/// calls, so repeatedly lexing small snippets does not pay for either again.
+pub struct Lexer {
+    parser: BoxedParser<'static, char, Vec<(Token, Span)>, Error>,
+    chars: Vec<(char, Span)>,
+    record_spacing: bool,
+    spacing: Vec<Option<OperatorSpacing>>,
+    record_escapes: bool,
+    escapes: Vec<Vec<(EscapeKind, Span)>>,
+}
+
+impl Lexer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(LexerConfig::default())
+    }
+
+    #[must_use]
+    pub fn with_config(config: LexerConfig) -> Self {
+        Self {
+            record_spacing: config.record_operator_spacing,
+            record_escapes: config.record_escapes,
+            parser: get_lexer_with_config(config).boxed(),
+            chars: Vec::new(),
+            spacing: Vec::new(),
+            escapes: Vec::new(),
+        }
+    }
+
+    /// Tokenizes `src`, clearing `out` and filling it with the resulting tokens.
+    /// The output is identical to that of [`get_lexer`].
+    ///
+    /// # Errors
+    /// * The source does not match Terbium grammar. `out` is left empty.
+    pub fn lex_into(&mut self, src: &str, out: &mut Vec<(Token, Span)>) -> Result<(), Vec<Error>> {
+        let source = super::Source::default();
+
+        out.clear();
+        self.spacing.clear();
+        self.escapes.clear();
+        self.chars.clear();
+        self.chars.extend(
+            src.chars()
+                .enumerate()
+                .map(|(i, c)| (c, Span::single(source.clone(), i))),
+        );
+
+        let tokens = self.parser.parse(chumsky::Stream::<_, Span, _>::from_iter(
+            Span::single(source, self.chars.len()),
+            self.chars.iter().cloned(),
+        ))?;
+
+        if self.record_spacing {
+            let is_space = |i: Option<usize>| {
+                i.and_then(|i| self.chars.get(i))
+                    .is_some_and(|(c, _)| c.is_whitespace())
+            };
+
+            self.spacing.extend(tokens.iter().map(|(token, span)| {
+                matches!(token, Token::Operator(_)).then(|| OperatorSpacing {
+                    leading: is_space(span.start().checked_sub(1)),
+                    trailing: is_space(Some(span.end())),
+                })
+            }));
+        }
+        if self.record_escapes {
+            self.escapes
+                .extend(tokens.iter().map(|(token, span)| match token {
+                    Token::Literal(Literal::String(s))
+                        if !matches!(s, StringLiteral::RawString(_)) =>
+                    {
+                        string_escapes(&self.chars[span.range()])
+                    }
+                    _ => Vec::new(),
+                }));
+        }
+        out.extend(tokens);
+
+        Ok(())
+    }
+
+    /// The whitespace around each token from the last call to [`Lexer::lex_into`], in the
+    /// same order as the tokens. Only operators have spacing, and only if
+    /// [`LexerConfig::record_operator_spacing`] is set; otherwise this is empty.
+    #[must_use]
+    pub fn operator_spacing(&self) -> &[Option<OperatorSpacing>] {
+        &self.spacing
+    }
+
+    /// The escape sequences within each token from the last call to [`Lexer::lex_into`], in
+    /// the same order as the tokens. Only string literals which process escapes have any, and
+    /// only if [`LexerConfig::record_escapes`] is set; otherwise this is empty.
+    #[must_use]
+    pub fn escapes(&self) -> &[Vec<(EscapeKind, Span)>] {
+        &self.escapes
+    }
+}
+
+/// Finds the escape sequences in the chars of a string literal token, including its prefix
+/// and quotes. The token is known to have lexed, so every escape is complete.
+fn string_escapes(chars: &[(char, Span)]) -> Vec<(EscapeKind, Span)> {
+    let quote = chars
+        .iter()
+        .position(|(c, _)| matches!(c, '"' | '\''))
+        .unwrap_or_default();
+    let prefix = chars[..quote].iter().map(|(c, _)| *c).collect::<String>();
+    let (raw, interpolated, percent) = (prefix.contains('r'), prefix.contains('$'), prefix == "u");
+
+    let mut escapes = Vec::new();
+    let mut depth = 0_usize;
+    let mut i = quote + 1;
+    // The last char is the closing quote
+    while i + 1 < chars.len() {
+        let next = chars.get(i + 1).map(|(c, _)| *c);
+        let escape = match chars[i].0 {
+            c @ ('{' | '}') if interpolated && depth == 0 && next == Some(c) => {
+                Some((EscapeKind::Brace, 2))
+            }
+            '{' if interpolated => {
+                depth += 1;
+                None
+            }
+            '}' if interpolated => {
+                depth -= 1;
+                None
+            }
+            _ if depth > 0 => None,
+            '%' if percent => Some((EscapeKind::Percent, if next == Some('%') { 2 } else { 3 })),
+            '\\' if !raw && !percent => Some(match next {
+                Some('x') => (EscapeKind::Hex, 4),
+                Some('u') => (EscapeKind::Unicode, 6),
+                Some('U') => (EscapeKind::Unicode, 10),
+                _ => (EscapeKind::Char, 2),
+            }),
+            _ => None,
+        };
+
+        match escape {
+            Some((kind, len)) => {
+                let span = chars[i].1.clone().merge(chars[i + len - 1].1.clone());
+
+                escapes.push((kind, span));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+
+    escapes
+}
+
+impl Default for Lexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Concatenates tokens, e.g. fragments of generated code, into a single stream along with
+/// source text for them.
+///
+/// Each token is given a fresh span within that text, so tokens taken from different
+/// fragments never have overlapping spans and diagnostics can point into
+/// [`TokenBuilder::to_source`]. Spans belong to [`Source::generated`] unless another source
+/// is given.
+#[derive(Clone, Debug, Default)]
+pub struct TokenBuilder {
+    source: super::Source,
+    text: String,
+    /// The length of `text` in chars, which is what spans count.
+    len: usize,
+    tokens: Vec<(Token, Span)>,
+}
+
+impl TokenBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_source(super::Source::generated())
+    }
+
+    #[must_use]
+    pub fn with_source(source: super::Source) -> Self {
+        Self {
+            source,
+            ..Self::default()
+        }
+    }
+
+    /// Appends `token`, separated from the previous one by a space, and returns the span it
+    /// was given.
+    pub fn push(&mut self, token: Token) -> Span {
+        if !self.tokens.is_empty() {
+            self.text.push(' ');
+            self.len += 1;
+        }
+
+        let text = match &token {
+            // Otherwise it would lex back as the keyword
+            Token::Identifier(name) if KEYWORDS.iter().any(|(text, _)| text == name) => {
+                format!("r#{}", name)
+            }
+            token => token.to_string(),
+        };
+        let start = self.len;
+        self.len += text.chars().count();
+        self.text.push_str(&text);
+
+        let span = Span::from_range(self.source.clone(), start..self.len);
+        self.tokens.push((token, span.clone()));
+        span
+    }
+
+    /// Appends every token of `fragment`, e.g. one lexed from another source. The spans of
+    /// the fragment are replaced by new ones.
+    pub fn append(&mut self, fragment: impl IntoIterator<Item = (Token, Span)>) {
+        for (token, _) in fragment {
+            self.push(token);
+        }
+    }
+
+    /// The source text of the tokens so far, which lexes back into the same tokens with the
+    /// same spans, as long as none of them is [`Token::Invalid`].
+    #[must_use]
+    pub fn to_source(&self) -> &str {
+        &self.text
+    }
+
+    /// The tokens so far along with their spans.
+    #[must_use]
+    pub fn tokens(&self) -> &[(Token, Span)] {
+        &self.tokens
+    }
+
+    #[must_use]
+    pub fn finish(self) -> TokenStream {
+        TokenStream(self.tokens)
+    }
+}
+
+/// A sequence of tokens along with their spans, as produced by [`lex`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TokenStream(pub Vec<(Token, Span)>);
+
+impl TokenStream {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Token, Span)> {
+        self.0.iter()
+    }
+
+    pub fn iter_tokens(&self) -> impl Iterator<Item = &Token> {
+        self.0.iter().map(|(token, _)| token)
+    }
+
+    pub fn iter_spans(&self) -> impl Iterator<Item = &Span> {
+        self.0.iter().map(|(_, span)| span)
+    }
+
+    /// The kind of each token, see [`Token::kind`].
+    pub fn kinds(&self) -> impl Iterator<Item = Discriminant<Token>> + '_ {
+        self.iter_tokens().map(Token::kind)
+    }
+
+    /// The tokens within the given range of indices.
+    ///
+    /// # Panics
+    /// * The range is out of bounds.
+    #[must_use]
+    pub fn slice(
+        &self,
+        range: impl SliceIndex<[(Token, Span)], Output = [(Token, Span)]>,
+    ) -> &[(Token, Span)] {
+        &self.0[range]
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[(Token, Span)] {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn into_vec(self) -> Vec<(Token, Span)> {
+        self.0
+    }
+}
+
+impl<I: SliceIndex<[(Token, Span)]>> Index<I> for TokenStream {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IntoIterator for TokenStream {
+    type Item = (Token, Span);
+    type IntoIter = std::vec::IntoIter<(Token, Span)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a TokenStream {
+    type Item = &'a (Token, Span);
+    type IntoIter = core::slice::Iter<'a, (Token, Span)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<(Token, Span)> for TokenStream {
+    fn from_iter<T: IntoIterator<Item = (Token, Span)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl From<Vec<(Token, Span)>> for TokenStream {
+    fn from(tokens: Vec<(Token, Span)>) -> Self {
+        Self(tokens)
+    }
+}
+
+/// Tokenizes the given source with the default configuration.
+///
+/// # Errors
+/// * The source does not match Terbium grammar.
+pub fn lex(src: &str) -> Result<TokenStream, Vec<Error>> {
+    lex_char_stream(src.chars(), src.chars().count())
+}
+
+/// Tokenizes the given source with the given configuration. Warnings, such as uses of
+/// [`LexerConfig::reserved_identifiers`], don't make lexing fail and are returned along
+/// with the tokens instead.
+///
+/// # Errors
+/// * The source does not match Terbium grammar.
+pub fn lex_with_warnings(
+    src: &str,
+    config: LexerConfig,
+) -> Result<(TokenStream, Vec<Error>), Vec<Error>> {
+    let source = super::Source::default();
+    let reserved = config.reserved_identifiers.clone();
+
+    let tokens = get_lexer_with_config(config).parse(chumsky::Stream::<_, Span, _>::from_iter(
+        Span::single(source.clone(), src.chars().count()),
+        src.chars()
+            .enumerate()
+            .map(move |(i, c)| (c, Span::single(source.clone(), i))),
+    ))?;
+    let warnings = tokens
+        .iter()
+        .filter_map(|(token, span)| match token {
+            Token::Identifier(s) if reserved.contains(s) => {
+                Some(Error::reserved_identifier(span.clone(), s))
+            }
+            _ => None,
+        })
+        .collect();
+
+    Ok((TokenStream(tokens), warnings))
+}
+
+/// Tokenizes source given as characters, e.g. coming out of a decoder, with the default
+/// configuration. Spans are char offsets, so the result is the same as with [`lex`] on the
+/// collected string.
+///
+/// The characters are lexed as they come, without being collected first.
+///
+/// # Errors
+/// * The source does not match Terbium grammar.
+pub fn lex_chars(chars: impl Iterator<Item = char>) -> Result<TokenStream, Vec<Error>> {
+    let source = super::Source::default();
+    let len = Cell::new(0);
+    // The end of the source isn't known before it is reached, so errors at the end get a
+    // placeholder span past any character, which is moved to the actual end afterwards
+    let placeholder = Span::single(source.clone(), usize::MAX - 1);
+
+    let tokens = get_lexer().parse(chumsky::Stream::<_, Span, _>::from_iter(
+        placeholder,
+        chars.map(|c| {
+            let offset = len.replace(len.get() + 1);
+            (c, Span::single(source.clone(), offset))
+        }),
+    ));
+
+    let len = len.get();
+    tokens.map(TokenStream).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|mut error| {
+                let (start, end) = error.span.range;
+                error.span.range = (start.min(len), end.min(len + 1));
+                error
+            })
+            .collect()
+    })
+}
+
+fn lex_char_stream(
+    chars: impl Iterator<Item = char>,
+    len: usize,
+) -> Result<TokenStream, Vec<Error>> {
+    let source = super::Source::default();
+
+    get_lexer()
+        .parse(chumsky::Stream::<_, Span, _>::from_iter(
+            Span::single(source.clone(), len),
+            chars
+                .enumerate()
+                .map(move |(i, c)| (c, Span::single(source.clone(), i))),
+        ))
+        .map(TokenStream)
+}
+
+/// Tokenizes source given as raw bytes, e.g. read from an untrusted file, which are first
+/// checked to be valid UTF-8.
+///
+/// Token spans are char offsets like with [`lex`]. The span of a UTF-8 error however covers
+/// the bytes of the first invalid sequence, since they don't form any characters.
+///
+/// # Errors
+/// * The bytes are not valid UTF-8.
+/// * The source does not match Terbium grammar.
+pub fn lex_bytes(bytes: &[u8]) -> Result<Vec<(Token, Span)>, Vec<Error>> {
+    let src = core::str::from_utf8(bytes).map_err(|e| {
+        let start = e.valid_up_to();
+        let (end, message) = e.error_len().map_or(
+            (bytes.len(), "source ends in the middle of a utf-8 sequence"),
+            |len| (start + len, "source is not valid utf-8"),
+        );
+
+        vec![Error::custom(
+            Span::from_range(super::Source::default(), start..end),
+            message,
+        )]
+    })?;
+
+    lex(src).map(TokenStream::into_vec)
+}
+
+/// The number of characters each thread lexes at minimum in [`lex_parallel`].
+#[cfg(feature = "rayon")]
+pub const PARALLEL_CHUNK_SIZE: usize = 1 << 16;
+
+/// Tokenizes `src` on rayon's thread pool, which is worthwhile for sources that are
+/// several megabytes large. The output is identical to that of [`get_lexer`].
+///
+/// # Errors
+/// * The source does not match Terbium grammar.
+#[cfg(feature = "rayon")]
+pub fn lex_parallel(src: &str) -> Result<Vec<(Token, Span)>, Vec<Error>> {
+    lex_parallel_with_chunk_size(src, PARALLEL_CHUNK_SIZE)
+}
+
+/// Same as [`lex_parallel`], but splits `src` into chunks of roughly `chunk_size` characters.
+///
+/// # Errors
+/// * The source does not match Terbium grammar.
+#[cfg(feature = "rayon")]
+pub fn lex_parallel_with_chunk_size(
+    src: &str,
+    chunk_size: usize,
+) -> Result<Vec<(Token, Span)>, Vec<Error>> {
+    use rayon::prelude::*;
+
+    let source = super::Source::default();
+    let chars = src.chars().collect::<Vec<_>>();
+    let boundaries = split_points(&chars, chunk_size);
+
+    // Spans are offset from the start of each chunk, so no correction is needed afterwards
+    let chunks = boundaries
+        .par_windows(2)
+        .map(|window| {
+            let (start, end) = (window[0], window[1]);
+
+            get_lexer().parse(chumsky::Stream::<_, Span, _>::from_iter(
+                Span::single(source.clone(), end),
+                chars[start..end]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (*c, Span::single(source.clone(), start + i))),
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+
+    for chunk in chunks {
+        match chunk {
+            Ok(chunk) => tokens.extend(chunk),
+            Err(chunk) => errors.extend(chunk),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Finds the offsets at which `chars` can be lexed independently, including the start and end.
+///
+/// Chunks only ever end right after a newline which isn't inside of a string or a comment.
+/// Lines starting with a comment are never split off either, since the lexer attaches
+/// comments to the token before them, and neither are lines which can't be told apart from
+/// one because `chars` ends first.
+#[cfg(feature = "rayon")]
+fn split_points(chars: &[char], chunk_size: usize) -> Vec<usize> {
+    let mut points = vec![0];
+    SplitScanner::new(chunk_size).scan(chars, true, &mut points);
+
+    points.push(chars.len());
+    points
+}
+
+#[cfg(any(feature = "std", feature = "rayon"))]
+#[derive(Copy, Clone, PartialEq)]
+enum ScanState {
+    Code,
+    /// A string closed by the given quote, and whether `\` escapes the next char in it
+    String(char, bool),
+    LineComment,
+    BlockComment,
+}
+
+/// Finds split points as described in [`split_points`] in source which may still be
+/// growing, remembering where it left off so that each char is only scanned once.
+#[cfg(any(feature = "std", feature = "rayon"))]
+struct SplitScanner {
+    state: ScanState,
+    /// The offset of the next char to scan
+    pos: usize,
+    /// The offset from which on the next split point may be
+    next: usize,
+    chunk_size: usize,
+}
+
+#[cfg(any(feature = "std", feature = "rayon"))]
+impl SplitScanner {
+    const fn new(chunk_size: usize) -> Self {
+        Self {
+            state: ScanState::Code,
+            pos: 0,
+            next: chunk_size,
+            chunk_size,
+        }
+    }
+
+    /// Scans the chars of `chars` which haven't been scanned yet and appends the split
+    /// points found to `points`. Unless `complete`, more chars may follow `chars` later, so
+    /// scanning stops early wherever it would have to look past its end.
+    fn scan(&mut self, chars: &[char], complete: bool, points: &mut Vec<usize>) {
+        let starts_with_code = |i: usize| {
+            let mut rest = chars[i..].iter().skip_while(|c| c.is_whitespace());
+
+            match (rest.next(), rest.next()) {
+                (Some('/'), Some('/' | '*')) => Some(false),
+                (Some('/'), None) | (None, _) if !complete => None,
+                (Some('/'), None) => Some(false),
+                (next, _) => Some(next.is_some()),
+            }
+        };
+
+        while self.pos < chars.len() {
+            let c = chars[self.pos];
+            let i = self.pos + 1;
+            let lookahead = chars.get(i);
+
+            if lookahead.is_none()
+                && !complete
+                && matches!(
+                    (self.state, c),
+                    (ScanState::Code, '/') | (ScanState::BlockComment, '*')
+                )
+            {
+                return;
+            }
+            self.pos = i;
+
+            match self.state {
+                ScanState::Code => match c {
+                    '"' => {
+                        self.state = ScanState::String(c, !has_raw_prefix(&chars[..i - 1]));
+                    }
+                    '\'' => self.state = ScanState::String(c, true),
+                    '/' if lookahead == Some(&'/') => {
+                        self.state = ScanState::LineComment;
+                        self.pos += 1;
+                    }
+                    '/' if lookahead == Some(&'*') => {
+                        self.state = ScanState::BlockComment;
+                        self.pos += 1;
+                    }
+                    _ => (),
+                },
+                ScanState::String(quote, escapes) => match c {
+                    '\\' if escapes => self.pos += 1,
+                    _ if c == quote => self.state = ScanState::Code,
+                    _ => (),
+                },
+                // Any of the line terminators recognized by `text::newline`
+                ScanState::LineComment
+                    if matches!(
+                        c,
+                        '\n' | '\r' | '\x0B' | '\x0C' | '\u{85}' | '\u{2028}' | '\u{2029}'
+                    ) =>
+                {
+                    self.state = ScanState::Code;
+                }
+                ScanState::BlockComment if c == '*' && lookahead == Some(&'/') => {
+                    self.state = ScanState::Code;
+                    self.pos += 1;
+                }
+                _ => continue,
+            }
+
+            if c == '\n' && self.state == ScanState::Code && i >= self.next {
+                match starts_with_code(i) {
+                    Some(true) => {
+                        points.push(i);
+                        self.next = i + self.chunk_size;
+                    }
+                    Some(false) => (),
+                    // Whether the next line starts with code is only known once more follows
+                    None => {
+                        self.pos = i - 1;
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forgets the first `n` chars, e.g. once they are lexed.
+    #[cfg(feature = "std")]
+    fn shift(&mut self, n: usize) {
+        self.pos = self.pos.saturating_sub(n);
+        self.next = self.next.saturating_sub(n);
+    }
+}
+
+/// Whether a string whose opening quote comes right after `before` doesn't process
+/// backslash escapes, i.e. it is a raw string `r"..."`, a raw interpolated string `r$"..."`
+/// or `$r"..."`, or a percent-encoded string `u"..."`.
+#[cfg(any(feature = "std", feature = "rayon"))]
+fn has_raw_prefix(before: &[char]) -> bool {
+    // The prefix must start a token rather than end an identifier, e.g. `bar"..."`
+    let starts_token = |len: usize| {
+        before.len() >= len
+            && !before[..before.len() - len]
+                .last()
+                .is_some_and(|c| is_ident_continue(*c))
+    };
+
+    match before {
+        [.., 'r', '$'] => starts_token(2),
+        [.., 'r' | 'u'] => starts_token(1),
+        _ => false,
+    }
+}
+
+/// Tokenizes source as it is read from `reader`, e.g. from a pipe, without reading all of it
+/// into memory first.
+///
+/// Source is lexed a line at a time, so only the current line is buffered, unless a string
+/// or comment spans several lines. Unlike the other lexers, spans are byte offsets from the
+/// start of the stream.
+///
+/// Reading stops after the first I/O error or invalid UTF-8, which is yielded as an [`Error`].
+#[cfg(feature = "std")]
+pub fn lex_reader(reader: impl Read) -> impl Iterator<Item = Result<(Token, Span), Error>> {
+    ReaderTokens {
+        reader,
+        bytes: Vec::new(),
+        chars: Vec::new(),
+        offset: 0,
+        scanner: SplitScanner::new(1),
+        pending: VecDeque::new(),
+        eof: false,
+    }
+}
+
+#[cfg(feature = "std")]
+struct ReaderTokens<R> {
+    reader: R,
+    /// Bytes which have been read but not decoded yet, i.e. a cut off multi-byte character
+    bytes: Vec<u8>,
+    /// Characters which have been decoded but not lexed yet
+    chars: Vec<char>,
+    /// The byte offset of the first of `chars` from the start of the stream
+    offset: usize,
+    scanner: SplitScanner,
+    pending: VecDeque<Result<(Token, Span), Error>>,
+    eof: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> ReaderTokens<R> {
+    fn read(&mut self) {
+        let mut buf = [0; 8192];
+
+        match self.reader.read(&mut buf) {
+            Ok(0) => self.eof = true,
+            Ok(n) => self.bytes.extend_from_slice(&buf[..n]),
+            Err(e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => self.fail(format!("failed to read source: {}", e)),
+        }
+    }
+
+    /// Moves as many bytes as are valid UTF-8 over to the characters.
+    fn decode(&mut self) {
+        // A multi-byte character may have been cut off by the end of the buffer
+        let (valid, invalid) = match core::str::from_utf8(&self.bytes) {
+            Ok(text) => (text.len(), false),
+            Err(e) => (e.valid_up_to(), e.error_len().is_some() || self.eof),
+        };
+
+        let text = core::str::from_utf8(&self.bytes[..valid]).unwrap();
+        self.chars.extend(text.chars());
+        self.bytes.drain(..valid);
+
+        if invalid {
+            self.fail("source is not valid utf-8".to_string());
+        }
+    }
+
+    /// Lexes whatever is left and reports an error right after it.
+    fn fail(&mut self, message: String) {
+        self.lex(self.chars.len());
+
+        let span = Span::single(super::Source::default(), self.offset);
+        self.pending.push_back(Err(Error::custom(span, message)));
+        self.bytes.clear();
+        self.eof = true;
+    }
+
+    /// Lexes the first `end` characters.
+    fn lex(&mut self, end: usize) {
+        let source = super::Source::default();
+        let mut offset = self.offset;
+
+        let tokens = get_lexer().parse(chumsky::Stream::<_, Span, _>::from_iter(
+            Span::single(
+                source.clone(),
+                offset
+                    + self.chars[..end]
+                        .iter()
+                        .map(|c| c.len_utf8())
+                        .sum::<usize>(),
+            ),
+            self.chars[..end].iter().map(|&c| {
+                let start = offset;
+                offset += c.len_utf8();
+                (c, Span::from_range(source.clone(), start..offset))
+            }),
+        ));
+
+        match tokens {
+            Ok(tokens) => self.pending.extend(tokens.into_iter().map(Ok)),
+            Err(errors) => self.pending.extend(errors.into_iter().map(Err)),
+        }
+
+        self.offset += self.chars.drain(..end).map(char::len_utf8).sum::<usize>();
+        self.scanner.shift(end);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Iterator for ReaderTokens<R> {
+    type Item = Result<(Token, Span), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            if self.eof && self.chars.is_empty() {
+                return None;
+            }
+            if !self.eof {
+                self.read();
+                self.decode();
+            }
+
+            if self.eof {
+                self.lex(self.chars.len());
+                continue;
+            }
+
+            // Only the newly decoded characters are scanned
+            let mut points = Vec::new();
+            self.scanner.scan(&self.chars, false, &mut points);
+
+            if let Some(&point) = points.last() {
+                self.lex(point);
+            }
+        }
+    }
+}
+
+/// The error for a character which doesn't start any token. Characters which do start a
+/// token elsewhere, such as the `$` of `$"{x}"`, get a more specific message.
+fn invalid_char(span: Span, c: char) -> Error {
+    match c {
+        '$' => Error::misplaced_dollar(span),
+        '#' => Error::misplaced_hash(span),
+        _ => Error::unexpected_token(span, &Token::Invalid(c)),
+    }
+}
+
+pub trait CharParser<T> = Parser<char, T, Error = Error> + Clone;
+
+/// Lexes a decimal integer literal, e.g. `123`.
+#[must_use]
+#[allow(clippy::cast_sign_loss)] // text::int does not handle signed
+pub fn integer() -> impl CharParser<Token> {
+    text::int::<_, Error>(10)
         .from_str::<i128>()
         // This is done to ensure that the interger won't overflow i128
         .unwrapped()
         .map(|int| Literal::Integer(int as u128))
         .map(Token::Literal)
-        .labelled("integer literal");
+        .labelled("integer literal")
+}
 
+/// Lexes a float literal, e.g. `1.5`, `1.` or `.5`.
+#[must_use]
+pub fn float() -> impl CharParser<Token> {
+    // A float's dot must never be followed by another dot, so that `1..2` is a range
+    // rather than the floats `1.` and `.2`. `1.` on its own is a float, but not when an
+    // identifier follows the dot: `1.foo` is an attribute of the integer `1`.
     let float = text::int::<_, Error>(10)
-        .chain::<char, _, _>(just('.').chain(filter(char::is_ascii_digit).repeated()))
+        .chain::<char, _, _>(
+            just('.')
+                .then_ignore(
+                    filter(|c: &char| *c != '.' && !is_ident_start(*c))
+                        .ignored()
+                        .or(end())
+                        .rewind(),
+                )
+                .chain(filter(char::is_ascii_digit).repeated()),
+        )
         .or(just('.').chain::<char, _, _>(text::digits(10)))
-        .collect::<String>()
+        .collect::<String>();
+
+    #[cfg(feature = "float")]
+    let float = float
+        .validate(|raw, span, emit| {
+            raw.parse().unwrap_or_else(|_| {
+                emit(Error::custom(span, "invalid float literal"));
+                Float {
+                    raw: raw.into_boxed_str(),
+                    value: f64::NAN,
+                }
+            })
+        })
         .map(Literal::Float)
-        .map(Token::Literal)
-        .labelled("float literal");
+        .map(Token::Literal);
+
+    // Still lex floats so that they aren't silently read as an integer and an attribute
+    #[cfg(not(feature = "float"))]
+    let float = float.validate(|_, span, emit| {
+        emit(Error::custom(span, "float literals are not supported"));
+        Token::Invalid('.')
+    });
+
+    float.labelled("float literal")
+}
+
+/// Lexes a string literal of any kind: quoted, raw, byte, interpolated and percent-encoded.
+#[must_use]
+pub fn string() -> impl CharParser<Token> {
+    let common_escape = just::<_, _, Error>('\\')
+        .or(just('"'))
+        .or(just('\''))
+        .or(just('b').to('\x08'))
+        .or(just('f').to('\x0C'))
+        .or(just('n').to('\n'))
+        .or(just('r').to('\r'))
+        .or(just('t').to('\t'))
+        .or(escape_hex!('x', 2));
 
     let escape = just::<_, _, Error>('\\')
         .ignore_then(
-            just('\\')
-                .or(just('"'))
-                .or(just('\''))
-                .or(just('b').to('\x08'))
-                .or(just('f').to('\x0C'))
-                .or(just('n').to('\n'))
-                .or(just('r').to('\r'))
-                .or(just('t').to('\t'))
-                .or(escape_hex!('x', 2))
+            common_escape
                 .or(escape_hex!('u', 4))
                 .or(escape_hex!('U', 8)),
         )
         .labelled("escape sequence");
 
-    let string = just::<_, _, Error>('"')
+    // Byte strings hold bytes rather than characters, so unicode escapes make no sense there
+    let byte_escape = just::<_, _, Error>('\\')
+        .ignore_then(
+            common_escape.or(one_of("uU")
+                .then(filter(char::is_ascii_hexdigit).repeated())
+                .validate(|_, span, emit| {
+                    emit(Error::custom(
+                        span,
+                        "unicode escape not allowed in byte string; use \\xNN",
+                    ));
+                    '\u{FFFD}'
+                })),
+        )
+        .labelled("escape sequence");
+
+    let byte_string = just::<_, _, Error>("~\"")
+        .ignore_then(
+            filter(|c: &char| *c != '\\' && *c != '"')
+                .or(byte_escape)
+                .repeated(),
+        )
+        .then_ignore(just('"'))
+        .collect::<String>()
+        .map(|s| Token::Literal(Literal::String(StringLiteral::ByteString(s))))
+        .labelled("byte string literal");
+
+    // Raw strings don't process escapes, so they can not contain a double quote
+    let raw_string = just::<_, _, Error>("r\"")
+        .ignore_then(none_of('"').repeated())
+        .then_ignore(just('"'))
+        .collect::<String>()
+        .map(|s| Token::Literal(Literal::String(StringLiteral::RawString(s))))
+        .labelled("raw string literal");
+
+    let quoted = just::<_, _, Error>('"')
         .ignore_then(
             filter(|c: &char| *c != '\\' && *c != '"')
                 .or(escape)
                 .repeated(),
         )
         .then_ignore(just::<_, char, _>('"'))
+        .collect::<String>()
+        .map(|s| (s, QuoteStyle::Double))
         .or(just('\'')
             .ignore_then(
                 filter(|c: &char| *c != '\\' && *c != '\'')
                     .or(escape)
                     .repeated(),
             )
-            .then_ignore(just::<_, char, _>('\'')))
-        .collect::<String>()
-        .map(|s| Token::Literal(Literal::String(StringLiteral::String(s))))
+            .then_ignore(just::<_, char, _>('\''))
+            .collect::<String>()
+            .map(|s| (s, QuoteStyle::Single)))
+        .map(|(s, quote)| Token::Literal(Literal::String(StringLiteral::String(s, quote))))
         .labelled("string literal");
 
-    let ident_or_keyword = text::ident().map(|s: String| match s.as_str() {
-        "func" => Token::Keyword(Keyword::Func),
-        "class" => Token::Keyword(Keyword::Class),
-        "require" => Token::Keyword(Keyword::Require),
-        "export" => Token::Keyword(Keyword::Export),
-        "let" => Token::Keyword(Keyword::Let),
-        "const" => Token::Keyword(Keyword::Const),
-        "mut" => Token::Keyword(Keyword::Mut),
-        "private" => Token::Keyword(Keyword::Private),
-        "if" => Token::Keyword(Keyword::If),
-        "else" => Token::Keyword(Keyword::Else),
-        "match" => Token::Keyword(Keyword::Match),
-        "for" => Token::Keyword(Keyword::For),
-        "in" => Token::Keyword(Keyword::In),
-        "while" => Token::Keyword(Keyword::While),
-        "break" => Token::Keyword(Keyword::Break),
-        "continue" => Token::Keyword(Keyword::Continue),
-        "return" => Token::Keyword(Keyword::Return),
-        "with" => Token::Keyword(Keyword::With),
-        "throws" => Token::Keyword(Keyword::Throws),
-        "where" => Token::Keyword(Keyword::Where),
-        _ => Token::Identifier(s),
+    // Interpolated strings are kept as templates: {{ and }} stay escaped and holes are kept
+    // verbatim, so that a literal brace can still be told apart from a hole.
+    let hole = recursive::<_, _, _, _, Error>(|hole| {
+        just('{')
+            .chain::<char, _, _>(
+                none_of("{}\"")
+                    .map(|c| vec![c])
+                    .or(hole)
+                    .repeated()
+                    .flatten(),
+            )
+            .chain::<char, _, _>(just('}'))
     });
 
-    let single_line = just::<_, _, Error>("//")
-        .then(take_until(text::newline().or(end())))
-        .ignored();
+    let brace_escape = just::<_, _, Error>("{{")
+        .or(just("}}"))
+        .map(|s| s.chars().collect::<Vec<_>>());
+
+    let template = |content: BoxedParser<'static, char, char, Error>| {
+        just('"')
+            .ignore_then(
+                brace_escape
+                    .or(hole.clone())
+                    // An escape sequence could also produce a brace
+                    .or(content.map(|c| match c {
+                        '{' | '}' => vec![c, c],
+                        _ => vec![c],
+                    }))
+                    .repeated()
+                    .flatten(),
+            )
+            .then_ignore(just('"'))
+            .collect::<String>()
+            .map(|s| Token::Literal(Literal::String(StringLiteral::InterpolatedString(s))))
+    };
+
+    // Raw interpolated strings, r$"..." or $r"...", don't process escapes in their literal chunks
+    let interpolated_string = just::<_, _, Error>("r$")
+        .or(just("$r"))
+        .ignore_then(template(none_of("{}\"").boxed()))
+        .or(just('$').ignore_then(template(
+            filter(|c: &char| !matches!(c, '\\' | '"' | '{' | '}'))
+                .or(escape)
+                .boxed(),
+        )))
+        .labelled("interpolated string literal");
+
+    // Percent-encoded bytes, i.e. %41 for A. %% is a literal percent sign.
+    let percent_escape = just::<_, _, Error>('%')
+        .ignore_then(
+            just('%').to(vec![b'%']).or(filter(|c: &char| *c != '"')
+                .repeated()
+                .at_most(2)
+                .collect::<String>()
+                .validate(|digits, span, emit| {
+                    if digits.len() == 2 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                        vec![u8::from_str_radix(&digits, 16).unwrap()]
+                    } else {
+                        emit(Error::custom(
+                            span,
+                            format!("invalid percent escape %{}", digits),
+                        ));
+                        '\u{FFFD}'.to_string().into_bytes()
+                    }
+                })),
+        )
+        .labelled("percent escape");
+
+    // u"..." strings only support percent escapes, which decode to UTF-8 like they do in URLs
+    let percent_string = just::<_, _, Error>("u\"")
+        .ignore_then(
+            filter(|c: &char| *c != '%' && *c != '"')
+                .map(|c| c.to_string().into_bytes())
+                .or(percent_escape)
+                .repeated()
+                .flatten(),
+        )
+        .then_ignore(just('"'))
+        .validate(|bytes, span, emit| {
+            String::from_utf8(bytes).unwrap_or_else(|e| {
+                emit(Error::custom(
+                    span,
+                    "percent escapes do not form valid utf-8",
+                ));
+                String::from_utf8_lossy(e.as_bytes()).into_owned()
+            })
+        })
+        .map(|s| {
+            Token::Literal(Literal::String(StringLiteral::String(
+                s,
+                QuoteStyle::Double,
+            )))
+        })
+        .labelled("percent-encoded string literal");
+
+    choice((
+        percent_string,
+        byte_string,
+        interpolated_string,
+        raw_string,
+        quoted,
+    ))
+}
+
+/// Lexes an identifier or a keyword, which is decided by `config`, e.g. through
+/// [`LexerConfig::keyword_aliases`].
+#[must_use]
+pub fn ident_or_keyword(config: &LexerConfig) -> impl CharParser<Token> {
+    let case_insensitive = config.case_insensitive_keywords;
+    let aliases = config.keyword_aliases.clone();
+    let float_constants = cfg!(feature = "float") && config.float_constants;
+    let max_len = config.max_ident_len.unwrap_or(usize::MAX);
+    // `r#` makes a raw identifier, which is never a keyword, e.g. `r#func`
+    let raw_prefix = just::<_, _, Error>("r#")
+        .then_ignore(filter(|c: &char| is_ident_start(*c)).rewind())
+        .or_not()
+        .map(|prefix| prefix.is_some());
+    raw_prefix
+        .then(
+            filter(|c: &char| is_ident_start(*c)).chain(
+                filter(|c: &char| is_ident_continue(*c))
+                    .repeated()
+                    .at_most(max_len.saturating_sub(1)),
+            ),
+        )
+        .map(|(raw, s)| (raw, s.into_iter().collect::<String>()))
+        // The rest is skipped without being collected
+        .then(
+            filter(|c: &char| is_ident_continue(*c))
+                .ignored()
+                .repeated()
+                .map(|rest| !rest.is_empty()),
+        )
+        .validate(|((raw, s), truncated), span, emit| {
+            if truncated {
+                emit(Error::custom(span, "identifier exceeds maximum length"));
+            }
+            (raw, s)
+        })
+        .map(move |(raw, s): (bool, String)| {
+            if raw {
+                return Token::Identifier(s);
+            }
+
+            let lowercase = case_insensitive.then(|| s.to_lowercase());
+            let name = lowercase.as_deref().unwrap_or(&s);
+
+            if let Some((_, keyword)) = KEYWORDS.iter().find(|(text, _)| *text == name) {
+                return Token::Keyword(keyword.clone());
+            }
+
+            match name {
+                "inf" | "nan" if float_constants => {
+                    Token::Literal(Literal::Float(name.parse().unwrap()))
+                }
+                _ => match aliases.get(name) {
+                    Some(keyword) => Token::Keyword(keyword.clone()),
+                    None => Token::Identifier(s),
+                },
+            }
+        })
+}
+
+/// Lexes an operator or punctuation symbol, e.g. `+=` or `(`. Operators outside of
+/// [`LexerConfig::allowed_operators`] become [`Token::Invalid`] with an error.
+#[must_use]
+pub fn operator_symbol(config: &LexerConfig) -> impl CharParser<Token> {
+    let allowed_operators = config.allowed_operators.clone();
+    static FLOOR_DIV: &[(&str, Token)] = &[
+        ("//=", Token::AugmentedAssign(Operator::FloorDiv)),
+        ("//", Token::Operator(Operator::FloorDiv)),
+    ];
+    let floor_div = match config.slash_slash {
+        SlashSlash::Comment => &[],
+        SlashSlash::FloorDiv => FLOOR_DIV,
+    };
+
+    // The floor division symbols are the longest of their length which start with a slash,
+    // so putting them first keeps the order sorted by length where it matters
+    floor_div
+        .iter()
+        .chain(SYMBOLS)
+        .map(|(text, token)| just(*text).to(token.clone()).boxed())
+        .reduce(|symbols, symbol| symbols.or(symbol).boxed())
+        .expect("symbol table is not empty")
+        .validate(move |token, span, emit| match token {
+            Token::Operator(op) | Token::AugmentedAssign(op)
+                if allowed_operators
+                    .as_ref()
+                    .is_some_and(|allowed| !allowed.contains(&op)) =>
+            {
+                emit(Error::custom(
+                    span,
+                    "operator not available in this dialect",
+                ));
+                Token::Invalid(op.as_str().chars().next().unwrap_or_default())
+            }
+            token => token,
+        })
+}
+
+/// Lexes and discards a line or block comment. What starts a line comment is decided by
+/// [`LexerConfig::slash_slash`] and [`LexerConfig::line_comment_prefix`].
+#[must_use]
+pub fn comment(config: &LexerConfig) -> impl CharParser<()> {
+    let line_comment_prefix = match config.slash_slash {
+        SlashSlash::Comment => Some(
+            config
+                .line_comment_prefix
+                .clone()
+                .unwrap_or_else(|| "//".to_string()),
+        ),
+        SlashSlash::FloorDiv => config.line_comment_prefix.clone(),
+    };
+    let single_line = match line_comment_prefix {
+        Some(prefix) => just::<_, _, Error>(prefix)
+            .then(take_until(text::newline().or(end())))
+            .ignored()
+            .boxed(),
+        // Never matches
+        None => filter(|_| false).ignored().boxed(),
+    };
 
     let multi_line = just::<_, _, Error>("/*")
         .then(take_until(just("*/")))
         .ignored();
 
-    let comment = single_line.or(multi_line).or_not();
-
-    let right_shift = just(">>").then_ignore(none_of(")<>]},;").rewind());
-
-    let symbol = choice::<_, Error>((
-        just(',').to(Token::Comma),
-        just(';').to(Token::Semicolon),
-        just('?').to(Token::Question),
-        just("::").to(Token::Cast),
-        just("..").map(|_| Token::Operator(Operator::Range)),
-        just('.').to(Token::Dot),
-        just('+').map(|_| Token::Operator(Operator::Add)),
-        just('-').map(|_| Token::Operator(Operator::Sub)),
-        just("**").map(|_| Token::Operator(Operator::Pow)),
-        just('*').map(|_| Token::Operator(Operator::Mul)),
-        just('/').map(|_| Token::Operator(Operator::Div)),
-        just('%').map(|_| Token::Operator(Operator::Mod)),
+    single_line.or(multi_line)
+}
+
+#[must_use]
+#[allow(clippy::result_large_err)] // chumsky takes errors by value
+pub fn get_lexer_with_config(
+    config: LexerConfig,
+) -> impl Parser<char, Vec<(Token, Span)>, Error = Error> {
+    let comment = comment(&config);
+
+    let invalid = |token, span, emit: &mut dyn FnMut(Error)| {
+        if let Token::Invalid(c) = token {
+            emit(invalid_char(span, c));
+        }
+        token
+    };
+    let unexpected = match config.recovery {
+        RecoveryStrategy::SkipChar => any().map(Token::Invalid).validate(invalid).boxed(),
+        RecoveryStrategy::SkipToWhitespace => any()
+            .then_ignore(filter(|c: &char| !c.is_whitespace()).repeated())
+            .map(Token::Invalid)
+            .validate(invalid)
+            .boxed(),
+        RecoveryStrategy::Abort => any().try_map(|c, span| Err(invalid_char(span, c))).boxed(),
+    };
+
+    let token = choice::<_, Error>((
+        string(),
+        float(),
+        operator_symbol(&config),
+        ident_or_keyword(&config),
+        integer(),
     ))
-    .or(choice((
-        // Weird split-off as chumsky only supports choices up to 26-length tuples.
-        // Maybe it would be better to separate them based off of category
-        just("==").map(|_| Token::Operator(Operator::Eq)),
-        just("!=").map(|_| Token::Operator(Operator::Ne)),
-        just('!').map(|_| Token::Operator(Operator::Not)), // Conflicts with !=
-        just('=').to(Token::Assign),                       // Conflicts with ==
-        just("<=").map(|_| Token::Operator(Operator::Le)),
-        just(">=").map(|_| Token::Operator(Operator::Ge)),
-        just("<<").to(Token::Operator(Operator::BitLShift)),
-        right_shift.to(Token::Operator(Operator::BitRShift)),
-        just('<').map(|_| Token::Operator(Operator::Lt)),
-        just('>').map(|_| Token::Operator(Operator::Gt)),
-        just("||").map(|_| Token::Operator(Operator::Or)),
-        just("&&").map(|_| Token::Operator(Operator::And)),
-        just('|').map(|_| Token::Operator(Operator::BitOr)),
-        just('^').map(|_| Token::Operator(Operator::BitXor)),
-        just('&').map(|_| Token::Operator(Operator::BitAnd)),
-        just('~').map(|_| Token::Operator(Operator::BitNot)),
-    )));
-
-    let brackets = choice::<_, Error>((
-        just('(').map(|_| Token::StartBracket(Bracket::Paren)),
-        just('[').map(|_| Token::StartBracket(Bracket::Bracket)),
-        just('{').map(|_| Token::StartBracket(Bracket::Brace)),
-        just(')').map(|_| Token::EndBracket(Bracket::Paren)),
-        just(']').map(|_| Token::EndBracket(Bracket::Bracket)),
-        just('}').map(|_| Token::EndBracket(Bracket::Brace)),
-    ));
+    .or(unexpected)
+    .map_with_span(move |token, span| (token, span))
+    .padded();
 
-    choice::<_, Error>((string, float, symbol, brackets, ident_or_keyword, integer))
-        .or(any().map(Token::Invalid).validate(|token, span, emit| {
-            emit(Error::unexpected_token(span, &token));
-            token
-        }))
-        .map_with_span(move |token, span| (token, span))
-        .padded()
-        .recover_with(skip_then_retry_until([]))
-        .padded_by(comment.padded())
+    // Without recovery, the first error ends the token stream and so fails `end`
+    let token = if config.recovery == RecoveryStrategy::Abort {
+        token.boxed()
+    } else {
+        token.recover_with(skip_then_retry_until([])).boxed()
+    };
+
+    token
+        .padded_by(comment.clone().padded().repeated())
         .repeated()
+        // Also covers sources which have comments but no tokens
+        .padded_by(comment.padded().repeated())
         .padded()
         .then_ignore(end())
 }
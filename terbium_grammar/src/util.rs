@@ -0,0 +1,571 @@
+use super::token::{lex, Bracket, Keyword, Literal, Operator, StringLiteral, Token};
+use super::{Error, ErrorKind, Source, Span, TargetKind};
+
+use core::ops::Range;
+
+/// Serializes tokens into an S-expression such as `(tokens (kw func) (ident main) (lparen))`,
+/// which is handy for debugging and for comparing token streams across tools.
+///
+/// When `with_spans` is set, each token is suffixed with its range, i.e. `(kw func @0..4)`.
+#[must_use]
+pub fn to_sexp(tokens: &[(Token, Span)], with_spans: bool) -> String {
+    let mut out = String::from("(tokens");
+
+    for (token, span) in tokens {
+        let (kind, value) = match token {
+            Token::Invalid(c) => ("invalid", Some(c.to_string())),
+            Token::Operator(o) => ("op", Some(o.to_string())),
+            Token::Literal(l) => (
+                match l {
+                    Literal::String(_) => "string",
+                    Literal::Integer(_) => "int",
+                    Literal::Float(_) => "float",
+                },
+                Some(l.to_string()),
+            ),
+            Token::Keyword(k) => ("kw", Some(k.to_string())),
+            Token::Identifier(i) => ("ident", Some(i.clone())),
+            Token::StartBracket(b) => (
+                match b {
+                    Bracket::Paren => "lparen",
+                    Bracket::Bracket => "lbracket",
+                    Bracket::Brace => "lbrace",
+                },
+                None,
+            ),
+            Token::EndBracket(b) => (
+                match b {
+                    Bracket::Paren => "rparen",
+                    Bracket::Bracket => "rbracket",
+                    Bracket::Brace => "rbrace",
+                },
+                None,
+            ),
+            Token::Comma => ("comma", None),
+            Token::Dot => ("dot", None),
+            Token::Cast => ("cast", None),
+            Token::Colon => ("colon", None),
+            Token::Question => ("question", None),
+            Token::At => ("at", None),
+            Token::Semicolon => ("semicolon", None),
+            Token::Assign => ("assign", None),
+            Token::AugmentedAssign(o) => ("augassign", Some(o.to_string())),
+        };
+
+        out.push_str(" (");
+        out.push_str(kind);
+        if let Some(value) = value {
+            out.push(' ');
+            out.push_str(&value);
+        }
+        if with_spans {
+            out.push_str(&format!(" @{:?}", span.range()));
+        }
+        out.push(')');
+    }
+
+    out.push(')');
+    out
+}
+
+/// Maps a token to the node type name a tree-sitter grammar would conventionally give it,
+/// so that highlighting queries can be shared with editor grammars.
+///
+/// Punctuation maps to its literal text, as anonymous nodes do in tree-sitter, and invalid
+/// characters map to `ERROR`.
+#[must_use]
+pub const fn tree_sitter_kind(token: &Token) -> &'static str {
+    match token {
+        Token::Invalid(_) => "ERROR",
+        Token::Operator(Operator::Not | Operator::BitNot | Operator::RefMut) => "unary_operator",
+        Token::Operator(_) => "binary_operator",
+        Token::Literal(Literal::String(s)) => match s {
+            StringLiteral::String(..) => "string_literal",
+            StringLiteral::ByteString(_) => "byte_string_literal",
+            StringLiteral::RawString(_) => "raw_string_literal",
+            StringLiteral::InterpolatedString(_) => "interpolated_string_literal",
+        },
+        Token::Literal(Literal::Integer(_)) => "integer_literal",
+        Token::Literal(Literal::Float(_)) => "float_literal",
+        Token::Keyword(Keyword::SelfKw) => "self",
+        Token::Keyword(Keyword::Super) => "super",
+        Token::Keyword(_) => "keyword",
+        Token::Identifier(_) => "identifier",
+        Token::StartBracket(Bracket::Paren) => "(",
+        Token::StartBracket(Bracket::Bracket) => "[",
+        Token::StartBracket(Bracket::Brace) => "{",
+        Token::EndBracket(Bracket::Paren) => ")",
+        Token::EndBracket(Bracket::Bracket) => "]",
+        Token::EndBracket(Bracket::Brace) => "}",
+        Token::Comma => ",",
+        Token::Dot => ".",
+        Token::Cast => "::",
+        Token::Colon => ":",
+        Token::Question => "?",
+        Token::At => "@",
+        Token::Semicolon => ";",
+        Token::Assign => "=",
+        Token::AugmentedAssign(_) => "augmented_assignment_operator",
+    }
+}
+
+/// How [`tokens_in_range`] treats tokens which are only partly inside of the range.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RangeMatch {
+    /// Only tokens lying entirely within the range.
+    Contained,
+    /// Any token sharing at least one offset with the range.
+    Intersecting,
+}
+
+/// Iterates over the tokens whose spans fall within `range`, e.g. the tokens in an
+/// editor selection. `range` uses the same offsets as the spans of `tokens`.
+pub fn tokens_in_range(
+    tokens: &[(Token, Span)],
+    range: Range<usize>,
+    mode: RangeMatch,
+) -> impl Iterator<Item = &(Token, Span)> {
+    tokens.iter().filter(move |(_, span)| match mode {
+        RangeMatch::Contained => span.is_within(&range),
+        RangeMatch::Intersecting => span.overlaps(&range),
+    })
+}
+
+pub type TokenIndex = usize;
+
+/// Finds the index of the token under `offset`, e.g. the cursor position for a hover.
+///
+/// Spans are end-exclusive, so when one token ends where the next starts, the latter is
+/// preferred. `tokens` must be sorted and non-overlapping, which the lexer guarantees.
+#[must_use]
+pub fn token_at(tokens: &[(Token, Span)], offset: usize) -> Option<TokenIndex> {
+    let index = tokens.partition_point(|(_, span)| span.end() <= offset);
+
+    tokens
+        .get(index)
+        .filter(|(_, span)| span.start() <= offset)
+        .map(|_| index)
+}
+
+/// Consecutive comments, as found by [`associate_comments`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommentGroup {
+    /// The text of each comment, including its delimiters.
+    pub comments: Vec<String>,
+    pub span: Span,
+    /// Whether the group follows its token on the same line, rather than preceding it.
+    pub trailing: bool,
+}
+
+/// Associates the comments in `src` with the tokens they document, e.g. for doc generators.
+///
+/// Comments on their own lines are grouped and attached to the next token, as long as no
+/// blank line separates them from it. A comment on the same line as a preceding token is
+/// attached to that token instead. Comments which are separated by a blank line from the next
+/// token are free-floating and aren't returned.
+#[must_use]
+pub fn associate_comments(src: &str, tokens: &[(Token, Span)]) -> Vec<(CommentGroup, TokenIndex)> {
+    let source = tokens
+        .first()
+        .map_or_else(Source::default, |(_, span)| span.src());
+    let chars = src.chars().collect::<Vec<_>>();
+    let newlines = |range: Range<usize>| chars[range].iter().filter(|c| **c == '\n').count();
+
+    let mut groups = Vec::new();
+    let mut group = Vec::<Range<usize>>::new();
+    let mut flush = |group: &mut Vec<Range<usize>>, index, trailing| {
+        if let (Some(first), Some(last)) = (group.first(), group.last()) {
+            let span = Span::from_range(source.clone(), first.start..last.end);
+            let comments = group
+                .drain(..)
+                .map(|range| chars[range].iter().collect())
+                .collect();
+
+            groups.push((
+                CommentGroup {
+                    comments,
+                    span,
+                    trailing,
+                },
+                index,
+            ));
+        }
+    };
+
+    for index in 0..=tokens.len() {
+        let start = index.checked_sub(1).map_or(0, |i| tokens[i].1.end());
+        let end = tokens
+            .get(index)
+            .map_or(chars.len(), |(_, span)| span.start());
+        let mut offset = start;
+
+        while let Some(comment) = next_comment(&chars, offset..end) {
+            if index > 0 && offset == start && newlines(start..comment.start) == 0 {
+                offset = comment.end;
+                group.push(comment);
+                flush(&mut group, index - 1, true);
+                continue;
+            }
+
+            if newlines(offset..comment.start) > 1 {
+                group.clear();
+            }
+
+            offset = comment.end;
+            group.push(comment);
+        }
+
+        if index < tokens.len() && newlines(offset..end) <= 1 {
+            flush(&mut group, index, false);
+        }
+        group.clear();
+    }
+
+    groups
+}
+
+/// Finds the next comment within `range` of `chars`, which must not contain any tokens.
+fn next_comment(chars: &[char], range: Range<usize>) -> Option<Range<usize>> {
+    let start = (range.start..range.end.saturating_sub(1))
+        .find(|&i| chars[i] == '/' && matches!(chars[i + 1], '/' | '*'))?;
+
+    let end = if chars[start + 1] == '/' {
+        (start..range.end)
+            .find(|&i| chars[i] == '\n')
+            .unwrap_or(range.end)
+    } else {
+        (start + 2..range.end.saturating_sub(1))
+            .find(|&i| chars[i] == '*' && chars[i + 1] == '/')
+            .map_or(range.end, |i| i + 2)
+    };
+
+    Some(start..end)
+}
+
+/// Whether a comment is a line comment, `// ...`, or a block comment, `/* ... */`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CommentKind {
+    Line,
+    Block,
+}
+
+/// Finds every comment in `src` without tokenizing it, e.g. for tools looking for `TODO`s.
+///
+/// Each comment comes with its text, which excludes the delimiters, and a span covering the
+/// whole comment. Comment delimiters within string literals are skipped, as are the contents
+/// of comments, so a `//` within a block comment is part of it. An unterminated block comment
+/// extends to the end of `src`. Use [`Span::to_byte_range`] to slice `src` by the span.
+#[must_use]
+pub fn extract_comments(src: &str) -> Vec<(CommentKind, String, Span)> {
+    let chars = src.chars().collect::<Vec<_>>();
+    let text = |range: Range<usize>| chars[range].iter().collect::<String>();
+    let span = |range| Span::from_range(Source::default(), range);
+
+    let mut comments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('/', Some('/')) => {
+                let end = (i..chars.len())
+                    .find(|&j| chars[j] == '\n')
+                    .unwrap_or(chars.len());
+                // Leave out the carriage return of CRLF line endings
+                let end = if end > i + 2 && chars[end - 1] == '\r' {
+                    end - 1
+                } else {
+                    end
+                };
+
+                comments.push((CommentKind::Line, text(i + 2..end), span(i..end)));
+                i = end;
+            }
+            ('/', Some('*')) => {
+                let (body_end, end) = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or((chars.len(), chars.len()), |j| (j, j + 2));
+
+                comments.push((CommentKind::Block, text(i + 2..body_end), span(i..end)));
+                i = end;
+            }
+            (quote @ ('"' | '\''), _) => {
+                // Raw strings, including r$"..." and $r"...", don't process escapes
+                let raw = quote == '"' && matches!(chars[..i], [.., 'r'] | [.., 'r', '$']);
+                i += 1;
+
+                while i < chars.len() && chars[i] != quote {
+                    i += if chars[i] == '\\' && !raw { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+
+    comments
+}
+
+/// Checks that the indentation of `src` means the same regardless of how wide a tab is,
+/// for use with off-side rule layouts where indentation is significant.
+///
+/// Like Python's tabnanny, each pair of adjacent lines is compared with tabs one and eight
+/// columns wide. If the lines compare differently, their indentation is ambiguous.
+///
+/// # Errors
+/// * An error for each line whose indentation is ambiguous compared to the line before it.
+pub fn check_indentation(src: &str) -> Result<(), Vec<Error>> {
+    fn width(indent: &[char], tab: usize) -> usize {
+        indent.iter().fold(0, |column, c| match c {
+            '\t' => (column / tab + 1) * tab,
+            _ => column + 1,
+        })
+    }
+
+    let mut errors = Vec::new();
+    let mut previous = Vec::new();
+    let mut offset = 0;
+
+    for line in src.split('\n') {
+        let chars = line.chars().collect::<Vec<_>>();
+        offset += chars.len() + 1;
+
+        let indent = chars
+            .iter()
+            .copied()
+            .take_while(|c| matches!(c, ' ' | '\t'))
+            .collect::<Vec<_>>();
+        if indent.len() == chars.len() || chars[indent.len()] == '\r' {
+            continue;
+        }
+
+        if width(&previous, 1).cmp(&width(&indent, 1))
+            != width(&previous, 8).cmp(&width(&indent, 8))
+        {
+            let start = offset - chars.len() - 1;
+            errors.push(Error::custom(
+                Span::from_range(Source::default(), start..start + indent.len()),
+                "inconsistent use of tabs and spaces in indentation",
+            ));
+        }
+        previous = indent;
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Checks that every bracket in the given tokens is matched, reporting all problems
+/// rather than only the first one so that editors can show them all at once.
+///
+/// A closing bracket without an opener is reported on its own and otherwise ignored,
+/// so one stray `)` does not cause every bracket after it to be reported as well.
+///
+/// # Errors
+/// * An error for each closing bracket which has no opening bracket.
+/// * An error for each opening bracket which is never closed.
+pub fn check_brackets(tokens: &[(Token, Span)]) -> Result<(), Vec<Error>> {
+    const fn name(bracket: Bracket) -> &'static str {
+        match bracket {
+            Bracket::Paren => "parenthesis",
+            Bracket::Bracket => "bracket",
+            Bracket::Brace => "brace",
+        }
+    }
+
+    let mut errors = Vec::new();
+    let mut open = Vec::<(&Bracket, &Span)>::new();
+
+    for (token, span) in tokens {
+        match token {
+            Token::StartBracket(bracket) => open.push((bracket, span)),
+            Token::EndBracket(bracket) => {
+                match open.iter().rposition(|(opener, _)| *opener == bracket) {
+                    // Anything opened after the matching opener was never closed
+                    Some(i) => errors.extend(open.drain(i..).skip(1).map(|(opener, span)| {
+                        Error::custom(span.clone(), format!("unclosed {}", name(*opener)))
+                    })),
+                    None => errors.push(Error::custom(
+                        span.clone(),
+                        format!("unmatched closing {}", name(*bracket)),
+                    )),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    errors.extend(
+        open.into_iter().map(|(opener, span)| {
+            Error::custom(span.clone(), format!("unclosed {}", name(*opener)))
+        }),
+    );
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        errors.sort_by_key(|e| e.span.start());
+        Err(errors)
+    }
+}
+
+/// Finds the regions of `src` which an editor can fold: every `{ ... }` block and, if
+/// `with_comments` is set, every block comment. Regions which fit on a single line aren't
+/// worth folding and are skipped, as are braces without a partner.
+///
+/// The regions are sorted by where they start.
+#[must_use]
+pub fn folding_ranges(src: &str, tokens: &[(Token, Span)], with_comments: bool) -> Vec<Span> {
+    let chars = src.chars().collect::<Vec<_>>();
+    let multiline = |range: &Range<usize>| {
+        chars
+            .get(range.clone())
+            .is_some_and(|chars| chars.contains(&'\n'))
+    };
+
+    let mut ranges = Vec::new();
+    let mut open = Vec::new();
+
+    for (token, span) in tokens {
+        match token {
+            Token::StartBracket(Bracket::Brace) => open.push(span),
+            Token::EndBracket(Bracket::Brace) => {
+                if let Some(start) = open.pop() {
+                    let range = start.start()..span.end();
+
+                    if multiline(&range) {
+                        ranges.push(Span::from_range(span.src(), range));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if with_comments {
+        let source = tokens
+            .first()
+            .map_or_else(Source::default, |(_, span)| span.src());
+
+        for index in 0..=tokens.len() {
+            let mut offset = index.checked_sub(1).map_or(0, |i| tokens[i].1.end());
+            let end = tokens
+                .get(index)
+                .map_or(chars.len(), |(_, span)| span.start());
+
+            while let Some(comment) = next_comment(&chars, offset..end) {
+                offset = comment.end;
+
+                if chars[comment.start + 1] == '*' && multiline(&comment) {
+                    ranges.push(Span::from_range(source.clone(), comment));
+                }
+            }
+        }
+    }
+
+    ranges.sort_by_key(Span::start);
+    ranges
+}
+
+/// Whether source typed so far is complete, e.g. a line entered into a REPL. See
+/// [`lex_status`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LexStatus {
+    /// The source lexes and every bracket is closed.
+    Complete,
+    /// A string literal is still open at the end of the source.
+    UnterminatedString,
+    /// A block comment is still open at the end of the source.
+    UnterminatedComment,
+    /// Brackets are still open at the end of the source. This holds the innermost one.
+    UnclosedBracket(Bracket),
+    /// The source has an error which no further input can fix, such as an unmatched
+    /// closing bracket.
+    Invalid,
+}
+
+impl LexStatus {
+    /// Whether more input could complete the source, i.e. whether a REPL should show a
+    /// continuation prompt rather than running what it has.
+    #[must_use]
+    pub const fn needs_continuation(self) -> bool {
+        matches!(
+            self,
+            Self::UnterminatedString | Self::UnterminatedComment | Self::UnclosedBracket(_)
+        )
+    }
+}
+
+/// Lexes `src` and reports whether it is complete, or which construct is left open at
+/// its end.
+#[must_use]
+pub fn lex_status(src: &str) -> LexStatus {
+    let tokens = match lex(src) {
+        Ok(tokens) => tokens,
+        Err(errors) => {
+            let chars = src.chars().collect::<Vec<_>>();
+
+            // A quote which doesn't start a valid string is unterminated if it is never
+            // closed, otherwise the string is invalid for some other reason
+            return if errors.iter().any(|e| match e.kind {
+                ErrorKind::Unexpected(TargetKind::Token(Token::Invalid(quote @ ('"' | '\'')))) => {
+                    !is_closed(&chars[e.span.end().min(chars.len())..], quote)
+                }
+                _ => false,
+            }) {
+                LexStatus::UnterminatedString
+            } else {
+                LexStatus::Invalid
+            };
+        }
+    };
+
+    let mut open = Vec::new();
+    let mut previous: Option<&(Token, Span)> = None;
+
+    for pair @ (token, span) in &tokens {
+        match token {
+            // An unterminated block comment isn't a comment, so it lexes as `/` and `*`
+            Token::Operator(Operator::Mul)
+                if previous.is_some_and(|(previous, previous_span)| {
+                    *previous == Token::Operator(Operator::Div)
+                        && previous_span.end() == span.start()
+                }) =>
+            {
+                return LexStatus::UnterminatedComment;
+            }
+            Token::StartBracket(bracket) => open.push(*bracket),
+            Token::EndBracket(bracket) if open.pop() != Some(*bracket) => {
+                return LexStatus::Invalid;
+            }
+            _ => (),
+        }
+        previous = Some(pair);
+    }
+
+    open.pop()
+        .map_or(LexStatus::Complete, LexStatus::UnclosedBracket)
+}
+
+/// Whether `src` is complete, see [`LexStatus::needs_continuation`].
+#[must_use]
+pub fn needs_continuation(src: &str) -> bool {
+    lex_status(src).needs_continuation()
+}
+
+/// Whether the string body in `chars` contains its closing `quote`, skipping escapes.
+fn is_closed(chars: &[char], quote: char) -> bool {
+    let mut chars = chars.iter();
+
+    while let Some(&c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            c if c == quote => return true,
+            _ => (),
+        }
+    }
+    false
+}
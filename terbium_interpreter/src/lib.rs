@@ -1,7 +1,5 @@
 //! The interpreter for Terbium.
 
-#![feature(box_patterns)]
-
 mod interner;
 
 use std::collections::HashMap;
@@ -125,7 +123,7 @@ impl ObjectStore {
     pub fn resolve(&self, loc: ObjectRef) -> &TerbiumObject {
         self.0
             .get(&loc)
-            .expect(&*format!("no object at location {:0x}", loc))
+            .unwrap_or_else(|| panic!("no object at location {:0x}", loc))
     }
 
     #[must_use]
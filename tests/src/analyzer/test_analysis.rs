@@ -4,10 +4,7 @@ use terbium::grammar::Source;
 #[test]
 fn test_analysis() {
     let mut a = BulkAnalyzer::new_with_analyzers(
-        vec!["non-snake-case"]
-            .iter()
-            .map(ToString::to_string)
-            .collect(),
+        ["non-snake-case"].iter().map(ToString::to_string).collect(),
     );
 
     a.analyze_string(
@@ -1 +1,41 @@
+pub mod test_cst;
+pub mod test_cursor;
+pub mod test_error;
+pub mod test_fold;
+pub mod test_keywords;
 pub mod test_lexer;
+pub mod test_lint;
+pub mod test_operator;
+pub mod test_parser;
+pub mod test_registry;
+pub mod test_sourcemap;
+pub mod test_span;
+pub mod test_token_stream;
+pub mod test_util;
+
+use terbium::grammar::token::{get_lexer_with_config, Token};
+use terbium::grammar::{ChumskyParser as _, ChumskyStream as Stream, LexerConfig, Source, Span};
+
+pub fn stream(code: &str) -> Stream<'_, char, Span, impl Iterator<Item = (char, Span)> + '_> {
+    Stream::from_iter(
+        Span::single(Source::default(), code.chars().count()),
+        code.chars()
+            .enumerate()
+            .map(|(i, c)| (c, Span::single(Source::default(), i))),
+    )
+}
+
+pub fn lex(code: &str) -> Vec<Token> {
+    lex_with_config(code, LexerConfig::default())
+}
+
+pub fn lex_with_config(code: &str, config: LexerConfig) -> Vec<Token> {
+    get_lexer_with_config(config)
+        .parse(stream(code))
+        .unwrap_or_else(|e| {
+            panic!("tokenization error: {:?}", e);
+        })
+        .into_iter()
+        .map(|(token, _)| token)
+        .collect()
+}
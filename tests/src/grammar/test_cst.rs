@@ -0,0 +1,58 @@
+use terbium::grammar::cst::{NodeKind, SyntaxElement, SyntaxKind, SyntaxNode};
+use terbium::grammar::token::Bracket;
+
+#[test]
+fn test_cst_is_lossless() {
+    let src = "
+        // Adds two numbers
+        func add(a,   b) {
+            a + b  /* sum */
+        }
+    ";
+    let root = SyntaxNode::from_source(src).unwrap();
+
+    assert_eq!(root.kind, NodeKind::Root);
+    assert_eq!(root.text(), src);
+    assert_eq!(root.range, 0..src.len());
+
+    let body = root
+        .descendants()
+        .find(|node| node.kind == NodeKind::Group(Bracket::Brace))
+        .unwrap();
+    assert_eq!(body.text(), &src[body.range.clone()]);
+    assert_eq!(body.text(), "{\n            a + b  /* sum */\n        }");
+
+    let params = root
+        .descendants()
+        .find(|node| node.kind == NodeKind::Group(Bracket::Paren))
+        .unwrap();
+    assert_eq!(params.text(), "(a,   b)");
+    assert!(matches!(
+        &params.children[3],
+        SyntaxElement::Token(token) if token.kind == SyntaxKind::Trivia && token.text == "   "
+    ));
+}
+
+#[test]
+fn test_strip_trivia() {
+    use terbium::grammar::cst::strip_trivia;
+    use terbium::grammar::token::lex;
+
+    let src = "
+        // Adds two numbers
+        func add(a,   b) {
+            a + b  /* sum */
+        }
+        add(1, [2]);
+    ";
+    let root = SyntaxNode::from_source(src).unwrap();
+
+    assert!(root.tokens().any(|token| token.kind.is_trivia()));
+    assert_eq!(
+        root.tokens()
+            .map(|token| token.text.as_str())
+            .collect::<String>(),
+        src
+    );
+    assert_eq!(strip_trivia(root.tokens()), lex(src).unwrap().into_vec());
+}
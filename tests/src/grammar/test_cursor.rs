@@ -0,0 +1,118 @@
+use terbium::grammar::cursor::Cursor;
+use terbium::grammar::token::{lex, Bracket};
+use terbium::grammar::{ErrorKind, Operator, TargetKind, Token};
+
+#[test]
+fn test_cursor_eat() {
+    let tokens = lex("(a, b)").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+
+    assert!(cursor.eat(&Token::Comma).is_none());
+    assert_eq!(cursor.position(), 0);
+    assert!(cursor.eat(&Token::StartBracket(Bracket::Paren)).is_some());
+    assert_eq!(
+        cursor.peek_kind(),
+        Some(Token::Identifier(String::new()).kind())
+    );
+    assert_eq!(
+        cursor.bump().map(|(token, _)| token),
+        Some(&Token::Identifier("a".to_string())),
+    );
+    assert!(cursor.eat(&Token::Comma).is_some());
+    assert_eq!(
+        cursor.peek_nth(1).map(|(token, _)| token),
+        Some(&Token::EndBracket(Bracket::Paren)),
+    );
+    assert_eq!(cursor.remaining().len(), 2);
+
+    cursor.bump();
+    cursor.bump();
+    assert!(cursor.is_at_end());
+    assert!(cursor.bump().is_none());
+    assert_eq!(cursor.peek_kind(), None);
+}
+
+#[test]
+fn test_cursor_split_shift() {
+    let tokens = lex("a >> b").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+
+    assert!(cursor.split_current_shift().is_none());
+    cursor.bump();
+    let (first, second) = cursor.split_current_shift().unwrap();
+    assert_eq!((first.range, second.range), ((2, 3), (3, 4)));
+    assert_eq!(
+        cursor.peek().map(|(token, _)| token),
+        Some(&Token::Identifier("b".to_string())),
+    );
+}
+
+#[test]
+fn test_cursor_expect() {
+    let tokens = lex("x + y").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+
+    cursor.bump();
+    let error = cursor.expect(&Token::Assign).unwrap_err();
+    assert_eq!(error.message(), "expected =, found + instead");
+    assert_eq!(error.span().range(), 2..3);
+    assert_eq!(
+        error.kind,
+        ErrorKind::Unexpected(TargetKind::Token(Token::Operator(Operator::Add)))
+    );
+    assert_eq!(cursor.position(), 1);
+
+    assert!(cursor.expect(&Token::Operator(Operator::Add)).is_ok());
+    cursor.bump();
+
+    let error = cursor.expect(&Token::Semicolon).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UnexpectedEnd);
+    assert_eq!(error.span().range(), 5..6);
+}
+
+#[test]
+fn test_cursor_kind() {
+    let identifier = Token::Identifier(String::new()).kind();
+    let tokens = lex("a b = c").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+
+    assert_eq!(
+        cursor.eat_kind(identifier).map(|(token, _)| token),
+        Some(&Token::Identifier("a".to_string())),
+    );
+    assert_eq!(
+        cursor.expect_kind(identifier).map(|(token, _)| token),
+        Ok(&Token::Identifier("b".to_string())),
+    );
+
+    assert!(cursor.eat_kind(identifier).is_none());
+    let error = cursor.expect_kind(identifier).unwrap_err();
+    assert_eq!(error.message(), "unexpected token =");
+    assert_eq!(error.span().range(), 4..5);
+    assert_eq!(cursor.position(), 2);
+
+    cursor.bump();
+    assert!(cursor.expect_kind(identifier).is_ok());
+    let error = cursor.expect_kind(identifier).unwrap_err();
+    assert_eq!(error.kind, ErrorKind::UnexpectedEnd);
+    assert_eq!(error.span().range(), 7..8);
+}
+
+#[test]
+fn test_cursor_backtracking() {
+    let tokens = lex("a = b; c").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+
+    // Speculatively parse `ident ( ... )` as a call, which fails at `=`
+    let checkpoint = cursor.save();
+    cursor.bump();
+    assert!(cursor.expect(&Token::StartBracket(Bracket::Paren)).is_err());
+    cursor.restore(checkpoint);
+
+    assert_eq!(cursor.position(), 0);
+    assert_eq!(
+        cursor.bump().map(|(token, _)| token),
+        Some(&Token::Identifier("a".to_string())),
+    );
+    assert!(cursor.expect(&Token::Assign).is_ok());
+}
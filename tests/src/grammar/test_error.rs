@@ -0,0 +1,116 @@
+use terbium::grammar::token::{Bracket, Token};
+use terbium::grammar::{ChumskyParser as _, Error, Expr, ParseInterface, Source, Span, TargetKind};
+
+use super::stream;
+
+#[test]
+fn test_error_accessors() {
+    let errors = terbium::grammar::tokenizer()
+        .parse(stream("1 ` 2"))
+        .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span().range(), 2..3);
+    assert_eq!(errors[0].message(), "unexpected token `");
+    assert!(errors[0].expected().is_empty());
+
+    let errors = Expr::from_string(Source::default(), "1 +".to_string()).unwrap_err();
+    assert!(errors[0]
+        .expected()
+        .contains(&&TargetKind::Token(Token::StartBracket(Bracket::Paren))));
+
+    let error = Error::custom(Span::single(Source::default(), 4), "something went wrong");
+    assert_eq!(error.span().range(), 4..5);
+    assert_eq!(error.message(), "something went wrong");
+    assert!(error.expected().is_empty());
+}
+
+#[test]
+fn test_misplaced_char() {
+    use std::collections::HashMap;
+    use terbium::grammar::token::{get_lexer_with_config, LexerConfig, RecoveryStrategy};
+    use terbium::grammar::ErrorCode;
+
+    let errors = |code: &str| {
+        terbium::grammar::tokenizer()
+            .parse(stream(code))
+            .unwrap_err()
+    };
+
+    let dollar = errors("1 $ 2");
+    assert_eq!(dollar.len(), 1);
+    assert_eq!(dollar[0].code, ErrorCode::MisplacedDollar);
+    assert_eq!(dollar[0].span().range(), 2..3);
+    assert_eq!(
+        dollar[0].message(),
+        "'$' is only valid as a string interpolation prefix, e.g. $\"{x}\""
+    );
+
+    let hash = errors("#x");
+    assert_eq!(hash[0].code, ErrorCode::MisplacedHash);
+    assert_eq!(
+        hash[0].message(),
+        "'#' is only valid in a raw identifier, e.g. r#func"
+    );
+
+    // Characters which mean nothing anywhere keep the generic message
+    let backtick = errors("1 ` 2");
+    assert_eq!(backtick[0].code, ErrorCode::UnexpectedToken);
+    assert_eq!(backtick[0].message(), "unexpected token `");
+
+    // The message is the same when lexing stops at the first error
+    let config = LexerConfig {
+        recovery: RecoveryStrategy::Abort,
+        ..LexerConfig::default()
+    };
+    let aborted = get_lexer_with_config(config)
+        .parse(stream("x #"))
+        .unwrap_err();
+    assert_eq!(aborted[0].message(), hash[0].message());
+
+    let mut error = errors("$").remove(0);
+    error.localize(&HashMap::new());
+    assert_eq!(error.message(), dollar[0].message());
+
+    // Each character has its own template, which can be translated as a whole
+    let mut error = hash.into_iter().next().unwrap();
+    error.localize(&HashMap::from([(
+        ErrorCode::MisplacedHash,
+        "'#' n'est valide que dans un identifiant brut, p. ex. r#func".to_string(),
+    )]));
+    assert_eq!(
+        error.message(),
+        "'#' n'est valide que dans un identifiant brut, p. ex. r#func"
+    );
+}
+
+#[test]
+fn test_localized_messages() {
+    use std::collections::HashMap;
+    use terbium::grammar::ErrorCode;
+
+    let mut error = terbium::grammar::tokenizer()
+        .parse(stream("1 ` 2"))
+        .unwrap_err()
+        .remove(0);
+    assert_eq!(error.code, ErrorCode::UnexpectedToken);
+
+    // Without overrides, the default templates reproduce the original message
+    error.localize(&HashMap::new());
+    assert_eq!(error.message(), "unexpected token `");
+
+    let messages = HashMap::from([(
+        ErrorCode::UnexpectedToken,
+        "jeton inattendu {0}".to_string(),
+    )]);
+    error.localize(&messages);
+    assert_eq!(error.message(), "jeton inattendu `");
+    assert_eq!(error.code, ErrorCode::UnexpectedToken);
+
+    let mut error = Expr::from_string(Source::default(), "1 +".to_string())
+        .unwrap_err()
+        .remove(0);
+    let original = error.message().to_string();
+    error.localize(&messages);
+    assert_eq!(error.code, ErrorCode::ExpectedFound);
+    assert_eq!(error.message(), original);
+}
@@ -0,0 +1,106 @@
+use terbium::grammar::fold::{eval_const, fold_constants, ConstValue, EvalError};
+use terbium::grammar::{Expr, Operator, ParseInterface, Source, Spanned};
+
+fn fold(code: &str) -> Expr {
+    fold_constants(Expr::from_string(Source::default(), code.to_string()).unwrap())
+}
+
+fn parse(code: &str) -> Expr {
+    Expr::from_string(Source::default(), code.to_string()).unwrap()
+}
+
+#[test]
+fn test_fold_constants() {
+    assert_eq!(fold("1 + 2 * 3"), Expr::Integer(7));
+    assert_eq!(fold("(1 + 2) * 3"), Expr::Integer(9));
+    assert_eq!(fold("2 ** 10 - 7 % 4"), Expr::Integer(1021));
+    // `**` is right-associative, so this is `2 ** 9` rather than `8 ** 2`
+    assert_eq!(fold("2 ** 3 ** 2"), Expr::Integer(512));
+    assert_eq!(fold("1 + 2 == 3"), Expr::Bool(true));
+    assert_eq!(fold("!(1 > 2) && true"), Expr::Bool(true));
+    assert_eq!(
+        fold("\"foo\" + \"bar\""),
+        Expr::String("foobar".to_string())
+    );
+
+    // These would fail at runtime, so they are left for the runtime to report
+    assert_eq!(fold("1 / 0"), parse("1 / 0"));
+    assert_eq!(fold("1 % 0"), parse("1 % 0"));
+    assert_eq!(fold("1 - 2"), parse("1 - 2"));
+    assert_eq!(
+        fold("2 ** 126 * 2"),
+        Expr::BinaryExpr {
+            operator: Spanned::new(Operator::Mul, Default::default()),
+            lhs: Spanned::new(Expr::Integer(1 << 126), Default::default()),
+            rhs: Spanned::new(Expr::Integer(2), Default::default()),
+        },
+    );
+    // Integers are `i128`s at runtime, even though literals are unsigned
+    let max = "170141183460469231731687303715884105727";
+    assert_eq!(fold(max), Expr::Integer(i128::MAX as u128));
+    assert_eq!(fold(&format!("{max} + 1")), parse(&format!("{max} + 1")));
+    assert_eq!(fold("2 ** 127"), parse("2 ** 127"));
+    assert_eq!(fold("1 << 127"), parse("1 << 127"));
+    assert_eq!(fold("1 + \"a\""), parse("1 + \"a\""));
+
+    assert_eq!(fold("a + 1"), parse("a + 1"));
+    assert_eq!(
+        fold("a + (1 + 2)"),
+        Expr::BinaryExpr {
+            operator: Spanned::new(Operator::Add, Default::default()),
+            lhs: Spanned::new(Expr::Ident("a".to_string()), Default::default()),
+            rhs: Spanned::new(Expr::Integer(3), Default::default()),
+        },
+    );
+    assert_eq!(fold("f(2 * 3)"), parse("f(6)"));
+}
+
+#[test]
+fn test_eval_const() {
+    let eval = |code| eval_const(&parse(code));
+
+    assert_eq!(eval("2 + 3 * 4"), Ok(ConstValue::Int(14)));
+    assert_eq!(eval("(2 + 3) * 4"), Ok(ConstValue::Int(20)));
+    assert_eq!(eval("2 ** 3 ** 2"), Ok(ConstValue::Int(512)));
+    assert_eq!(eval("1 - 2"), Ok(ConstValue::Int(-1)));
+    assert_eq!(eval("7 / 2"), Ok(ConstValue::Int(3)));
+    assert_eq!(eval("-7 / 2"), Ok(ConstValue::Int(-3)));
+    assert_eq!(eval("-7 % 2"), Ok(ConstValue::Int(-1)));
+    assert_eq!(eval("1 << 4 | 1"), Ok(ConstValue::Int(17)));
+    assert_eq!(
+        eval("\"foo\" + \"bar\""),
+        Ok(ConstValue::Str("foobar".to_string()))
+    );
+
+    assert_eq!(eval("1 + 2 == 3"), Ok(ConstValue::Bool(true)));
+    assert_eq!(eval("2 * 3 < 5"), Ok(ConstValue::Bool(false)));
+    assert_eq!(
+        eval("!(1 > 2) && \"a\" < \"b\""),
+        Ok(ConstValue::Bool(true))
+    );
+    // The right-hand side is never evaluated
+    assert_eq!(eval("false && 1 / 0 == 1"), Ok(ConstValue::Bool(false)));
+
+    assert_eq!(eval("1 / 0"), Err(EvalError::DivisionByZero));
+    assert_eq!(eval("1 % (2 - 2)"), Err(EvalError::DivisionByZero));
+    assert_eq!(eval("2 ** 127"), Err(EvalError::Overflow));
+    assert_eq!(eval("1 << 127"), Err(EvalError::Overflow));
+    assert_eq!(eval("3 << 126"), Err(EvalError::Overflow));
+    assert_eq!(eval("-1 << 127"), Ok(ConstValue::Int(i128::MIN)));
+    assert_eq!(
+        eval_const(&Expr::Integer(1 << 127)),
+        Err(EvalError::Overflow)
+    );
+    assert_eq!(
+        eval("1 + \"a\""),
+        Err(EvalError::InvalidOperands(Operator::Add))
+    );
+    assert_eq!(eval("a + 1"), Err(EvalError::NotConstant));
+    assert_eq!(eval("f(1)"), Err(EvalError::NotConstant));
+
+    #[cfg(feature = "float")]
+    {
+        assert_eq!(eval("1 + 0.5"), Ok(ConstValue::Float(1.5)));
+        assert_eq!(eval("1.5 / 0"), Err(EvalError::DivisionByZero));
+    }
+}
@@ -0,0 +1,522 @@
+use std::collections::{HashMap, HashSet};
+use terbium::grammar::token::{get_lexer_with_config, lex_with_warnings, Keyword, Literal, Token};
+use terbium::grammar::{
+    ChumskyParser as _, Error, LexerConfig, Operator, Severity, SlashSlash, Source, Span,
+};
+
+use super::{lex, lex_with_config, stream};
+
+fn ident(s: &str) -> Token {
+    Token::Identifier(s.to_string())
+}
+
+#[test]
+fn test_from_keyword() {
+    assert_eq!(
+        lex("from math require sin"),
+        vec![
+            Token::Keyword(Keyword::From),
+            ident("math"),
+            Token::Keyword(Keyword::Require),
+            ident("sin"),
+        ],
+    );
+    assert_eq!(lex("fromage"), vec![ident("fromage")]);
+    assert!(Keyword::From.is_soft());
+}
+
+#[test]
+fn test_as_keyword() {
+    assert_eq!(
+        lex("require foo as bar"),
+        vec![
+            Token::Keyword(Keyword::Require),
+            ident("foo"),
+            Token::Keyword(Keyword::As),
+            ident("bar"),
+        ],
+    );
+    assert_eq!(lex("assert"), vec![ident("assert")]);
+    assert!(!Keyword::As.is_soft());
+}
+
+#[test]
+fn test_is_keyword() {
+    assert_eq!(
+        lex("x is Int"),
+        vec![ident("x"), Token::Keyword(Keyword::Is), ident("Int")],
+    );
+    assert_eq!(lex("island"), vec![ident("island")]);
+    assert!(Keyword::Is.is_soft());
+}
+
+#[test]
+fn test_async_await_keywords() {
+    assert_eq!(
+        lex("async func f() { await g(); }")[..2],
+        [
+            Token::Keyword(Keyword::Async),
+            Token::Keyword(Keyword::Func)
+        ],
+    );
+    assert_eq!(lex("await")[0], Token::Keyword(Keyword::Await));
+    assert_eq!(
+        lex("awaited asynchronous"),
+        vec![ident("awaited"), ident("asynchronous")],
+    );
+    assert!(!Keyword::Async.is_soft());
+    assert!(!Keyword::Await.is_soft());
+}
+
+#[test]
+fn test_try_catch_finally_keywords() {
+    assert_eq!(
+        lex("try catch finally"),
+        vec![
+            Token::Keyword(Keyword::Try),
+            Token::Keyword(Keyword::Catch),
+            Token::Keyword(Keyword::Finally),
+        ],
+    );
+    assert_eq!(
+        lex("trying catcher"),
+        vec![ident("trying"), ident("catcher")]
+    );
+    assert!(Keyword::Try.is_soft() && Keyword::Catch.is_soft() && Keyword::Finally.is_soft());
+}
+
+#[test]
+fn test_type_declaration_keywords() {
+    assert_eq!(
+        lex("enum struct trait impl"),
+        vec![
+            Token::Keyword(Keyword::Enum),
+            Token::Keyword(Keyword::Struct),
+            Token::Keyword(Keyword::Trait),
+            Token::Keyword(Keyword::Impl),
+        ],
+    );
+    assert_eq!(
+        lex("implementation structure traits enums"),
+        vec![
+            ident("implementation"),
+            ident("structure"),
+            ident("traits"),
+            ident("enums"),
+        ],
+    );
+    assert!(!Keyword::Enum.is_soft());
+    assert!(!Keyword::Impl.is_soft());
+}
+
+#[test]
+fn test_type_keyword() {
+    assert_eq!(
+        lex("type Id = Int"),
+        vec![
+            Token::Keyword(Keyword::Type),
+            ident("Id"),
+            Token::Assign,
+            ident("Int"),
+        ],
+    );
+    assert_eq!(lex("types"), vec![ident("types")]);
+    assert!(Keyword::Type.is_soft());
+}
+
+#[test]
+fn test_loop_do_keywords() {
+    assert_eq!(
+        lex("loop do"),
+        vec![Token::Keyword(Keyword::Loop), Token::Keyword(Keyword::Do)],
+    );
+    assert_eq!(
+        lex("loopback domain"),
+        vec![ident("loopback"), ident("domain")]
+    );
+    assert!(!Keyword::Loop.is_soft() && !Keyword::Do.is_soft());
+}
+
+#[test]
+fn test_yield_keyword() {
+    assert_eq!(
+        lex("yield value"),
+        vec![Token::Keyword(Keyword::Yield), ident("value")],
+    );
+    assert_eq!(lex("yielded"), vec![ident("yielded")]);
+    assert!(Keyword::Yield.is_soft());
+}
+
+#[test]
+fn test_defer_keyword() {
+    assert_eq!(lex("defer close();")[0], Token::Keyword(Keyword::Defer),);
+    assert_eq!(lex("deferred"), vec![ident("deferred")]);
+    assert!(Keyword::Defer.is_soft());
+}
+
+#[test]
+fn test_self_super_keywords() {
+    assert_eq!(
+        lex("self.x super"),
+        vec![
+            Token::Keyword(Keyword::SelfKw),
+            Token::Dot,
+            ident("x"),
+            Token::Keyword(Keyword::Super),
+        ],
+    );
+    assert_eq!(Keyword::SelfKw.to_string(), "self");
+    assert_eq!(lex("selfie superb"), vec![ident("selfie"), ident("superb")]);
+    assert!(!Keyword::SelfKw.is_soft() && !Keyword::Super.is_soft());
+}
+
+#[test]
+fn test_pub_keyword() {
+    assert_eq!(
+        lex("pub func"),
+        vec![Token::Keyword(Keyword::Pub), Token::Keyword(Keyword::Func)],
+    );
+    assert_eq!(
+        lex("public published"),
+        vec![ident("public"), ident("published")]
+    );
+    // Visibility modifiers are soft, just like `private`
+    assert_eq!(Keyword::Pub.is_soft(), Keyword::Private.is_soft());
+}
+
+#[test]
+fn test_static_keyword() {
+    assert_eq!(lex("static X = 1")[0], Token::Keyword(Keyword::Static),);
+    assert_eq!(lex("statically"), vec![ident("statically")]);
+    assert!(!Keyword::Static.is_soft());
+}
+
+#[test]
+fn test_unsafe_keyword() {
+    assert_eq!(lex("unsafe {}")[0], Token::Keyword(Keyword::Unsafe));
+    assert_eq!(lex("unsafely"), vec![ident("unsafely")]);
+    assert!(!Keyword::Unsafe.is_soft());
+}
+
+#[test]
+fn test_case_insensitive_keywords() {
+    let config = LexerConfig {
+        case_insensitive_keywords: true,
+        ..LexerConfig::default()
+    };
+
+    assert_eq!(
+        lex_with_config("WHILE If Foo", config),
+        vec![
+            Token::Keyword(Keyword::While),
+            Token::Keyword(Keyword::If),
+            ident("Foo"),
+        ],
+    );
+    assert_eq!(lex("WHILE"), vec![ident("WHILE")]);
+}
+
+#[test]
+fn test_keyword_aliases() {
+    let config = LexerConfig {
+        keyword_aliases: HashMap::from([
+            ("fn".to_string(), Keyword::Func),
+            ("import".to_string(), Keyword::Require),
+        ]),
+        ..LexerConfig::default()
+    };
+
+    assert_eq!(
+        lex_with_config("fn import func", config),
+        vec![
+            Token::Keyword(Keyword::Func),
+            Token::Keyword(Keyword::Require),
+            Token::Keyword(Keyword::Func),
+        ],
+    );
+    assert_eq!(lex("fn import"), vec![ident("fn"), ident("import")]);
+}
+
+#[test]
+fn test_reserved_identifiers() {
+    let config = LexerConfig {
+        reserved_identifiers: HashSet::from(["spawn".to_string()]),
+        ..LexerConfig::default()
+    };
+
+    // Reserved identifiers are only warned about, so lexing still succeeds
+    let tokens = get_lexer_with_config(config.clone()).parse(stream("spawn x"));
+    assert_eq!(
+        tokens.map(|t| t.into_iter().map(|(t, _)| t).collect::<Vec<_>>()),
+        Ok(vec![ident("spawn"), ident("x")]),
+    );
+
+    let (tokens, warnings) = lex_with_warnings("spawn x; r#spawn", config).unwrap();
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].severity, Severity::Warning);
+    assert!(warnings.iter().all(Error::is_warning));
+    assert_eq!(warnings[0].span.range(), 0..5);
+    assert_eq!(warnings[1].span.range(), 9..16);
+
+    assert_eq!(lex("spawn"), vec![ident("spawn")]);
+}
+
+#[test]
+fn test_declaration_keywords() {
+    let declarations = lex("func class enum struct trait impl type let const static");
+    let others = lex("mut pub private unsafe if return require export as async self");
+
+    for token in declarations {
+        assert!(matches!(token, Token::Keyword(k) if k.starts_declaration()));
+    }
+    for token in others {
+        assert!(matches!(token, Token::Keyword(k) if !k.starts_declaration()));
+    }
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn test_float_constants() {
+    use terbium::grammar::token::{Literal, Operator};
+
+    let config = LexerConfig {
+        float_constants: true,
+        ..LexerConfig::default()
+    };
+    let tokens = lex_with_config("inf -inf nan info", config);
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Literal(Literal::Float("inf".parse().unwrap())),
+            Token::Operator(Operator::Sub),
+            Token::Literal(Literal::Float("inf".parse().unwrap())),
+            Token::Literal(Literal::Float("nan".parse().unwrap())),
+            ident("info"),
+        ],
+    );
+
+    let value = |token: &Token| match token {
+        Token::Literal(literal) => literal.as_f64().unwrap(),
+        _ => unreachable!(),
+    };
+    assert_eq!(value(&tokens[0]), f64::INFINITY);
+    assert!(value(&tokens[3]).is_nan());
+    assert_eq!(Literal::Float("2.5".parse().unwrap()).as_f64(), Some(2.5));
+
+    assert_eq!(lex("inf nan"), vec![ident("inf"), ident("nan")]);
+}
+
+#[test]
+fn test_max_ident_len() {
+    let config = LexerConfig {
+        max_ident_len: Some(8),
+        ..LexerConfig::default()
+    };
+
+    assert_eq!(
+        lex_with_config("exactly8 x", config.clone()),
+        vec![ident("exactly8"), ident("x")],
+    );
+
+    let (tokens, errors) = get_lexer_with_config(config).parse_recovery(stream("much_too_long x"));
+    assert_eq!(
+        tokens.unwrap(),
+        vec![
+            (
+                ident("much_too"),
+                Span::from_range(Source::default(), 0..13)
+            ),
+            (ident("x"), Span::from_range(Source::default(), 14..15)),
+        ]
+    );
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "identifier exceeds maximum length");
+
+    assert_eq!(lex("much_too_long"), vec![ident("much_too_long")]);
+}
+
+#[test]
+fn test_allowed_operators() {
+    use Operator::*;
+
+    assert_eq!(
+        lex("a | b"),
+        vec![ident("a"), Token::Operator(BitOr), ident("b")],
+    );
+
+    let config = LexerConfig {
+        allowed_operators: Some(
+            [Add, Sub, Mul, Div, Eq, Ne, Lt, Gt, And, Or, Not]
+                .into_iter()
+                .collect(),
+        ),
+        ..LexerConfig::default()
+    };
+
+    assert_eq!(
+        lex_with_config("a || b + c", config.clone()),
+        vec![
+            ident("a"),
+            Token::Operator(Or),
+            ident("b"),
+            Token::Operator(Add),
+            ident("c"),
+        ],
+    );
+
+    for code in ["a | b", "a |= b", "a << b", "~a"] {
+        let (_, errors) = get_lexer_with_config(config.clone()).parse_recovery(stream(code));
+
+        assert_eq!(errors.len(), 1, "{:?}", code);
+        assert_eq!(
+            errors[0].message(),
+            "operator not available in this dialect"
+        );
+    }
+
+    let (tokens, _) = get_lexer_with_config(config).parse_recovery(stream("a | b"));
+    assert_eq!(tokens.unwrap()[1].1, Span::single(Source::default(), 2));
+}
+
+#[test]
+fn test_slash_slash() {
+    let code = "a // b\nc //= 2 # note\n";
+
+    // By default `//` starts a comment, even in `//=`
+    assert_eq!(lex(code), vec![ident("a"), ident("c")]);
+
+    let floor_div = LexerConfig {
+        slash_slash: SlashSlash::FloorDiv,
+        line_comment_prefix: Some("#".to_string()),
+        ..LexerConfig::default()
+    };
+    assert_eq!(
+        lex_with_config(code, floor_div.clone()),
+        vec![
+            ident("a"),
+            Token::Operator(Operator::FloorDiv),
+            ident("b"),
+            ident("c"),
+            Token::AugmentedAssign(Operator::FloorDiv),
+            Token::Literal(Literal::Integer(2)),
+        ],
+    );
+    assert_eq!(
+        lex_with_config("a / b /= c /* block */", floor_div),
+        vec![
+            ident("a"),
+            Token::Operator(Operator::Div),
+            ident("b"),
+            Token::AugmentedAssign(Operator::Div),
+            ident("c"),
+        ],
+    );
+
+    // Without a prefix there are no line comments
+    let no_comments = LexerConfig {
+        slash_slash: SlashSlash::FloorDiv,
+        ..LexerConfig::default()
+    };
+    assert_eq!(
+        lex_with_config("7 // 2", no_comments),
+        vec![
+            Token::Literal(Literal::Integer(7)),
+            Token::Operator(Operator::FloorDiv),
+            Token::Literal(Literal::Integer(2)),
+        ],
+    );
+}
+
+#[test]
+fn test_recovery_strategy() {
+    use terbium::grammar::token::RecoveryStrategy;
+
+    let code = "a #b c ##";
+    let recover = |recovery| {
+        let config = LexerConfig {
+            recovery,
+            ..LexerConfig::default()
+        };
+        let (tokens, errors) = get_lexer_with_config(config).parse_recovery(stream(code));
+        let tokens = tokens.map(|t| t.into_iter().map(|(t, _)| t).collect::<Vec<_>>());
+
+        (
+            tokens,
+            errors.into_iter().map(|e| e.span.range).collect::<Vec<_>>(),
+        )
+    };
+
+    // Each `#` is an error of its own and `b` is still lexed
+    assert_eq!(
+        recover(RecoveryStrategy::default()),
+        (
+            Some(vec![
+                ident("a"),
+                Token::Invalid('#'),
+                ident("b"),
+                ident("c"),
+                Token::Invalid('#'),
+                Token::Invalid('#'),
+            ]),
+            vec![(2, 3), (7, 8), (8, 9)],
+        ),
+    );
+    assert_eq!(
+        recover(RecoveryStrategy::SkipToWhitespace),
+        (
+            Some(vec![
+                ident("a"),
+                Token::Invalid('#'),
+                ident("c"),
+                Token::Invalid('#'),
+            ]),
+            vec![(2, 4), (7, 9)],
+        ),
+    );
+
+    let (tokens, errors) = recover(RecoveryStrategy::Abort);
+    assert_eq!(tokens, None);
+    assert_eq!(errors, [(2, 3)]);
+}
+
+#[test]
+fn test_raw_identifiers() {
+    assert_eq!(
+        lex("r#func r#x r"),
+        vec![ident("func"), ident("x"), ident("r")]
+    );
+}
+
+#[test]
+fn test_keyword_as_identifier_text() {
+    assert_eq!(Keyword::Where.as_identifier_text(), Some("where"));
+    assert_eq!(Keyword::Require.as_identifier_text(), Some("require"));
+    assert_eq!(Keyword::If.as_identifier_text(), None);
+    assert_eq!(Keyword::With.as_identifier_text(), None);
+}
+
+#[test]
+fn test_as_str() {
+    use terbium::grammar::token::{fixed_token_spellings, KEYWORDS};
+
+    for (text, keyword) in KEYWORDS {
+        assert_eq!(keyword.as_str(), *text);
+        assert_eq!(keyword.as_str(), keyword.to_string());
+    }
+
+    let operators = fixed_token_spellings()
+        .iter()
+        .filter_map(|(token, text)| match token {
+            Token::Operator(op) => Some((*op, *text)),
+            _ => None,
+        })
+        .chain([(Operator::RefMut, "&mut")])
+        .collect::<Vec<_>>();
+    assert!(operators.len() > 20);
+
+    for (op, text) in operators {
+        assert_eq!(op.as_str(), text);
+        assert_eq!(op.as_str(), op.to_string());
+    }
+}
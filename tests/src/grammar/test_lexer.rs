@@ -1,5 +1,7 @@
 use terbium::grammar::token::*;
-use terbium::grammar::{ChumskyParser as _, ChumskyStream as Stream, Source, Span};
+use terbium::grammar::{ChumskyParser, ChumskyStream as Stream, Error, Source, Span};
+
+use super::{lex, lex_with_config, stream};
 
 #[test]
 fn test_lexer() {
@@ -32,7 +34,8 @@ fn test_lexer() {
             Token::Identifier("println".to_string()),
             Token::StartBracket(Bracket::Paren),
             Token::Literal(Literal::String(StringLiteral::String(
-                "Hello, world!".to_string()
+                "Hello, world!".to_string(),
+                QuoteStyle::Double,
             ))),
             Token::EndBracket(Bracket::Paren),
             Token::Semicolon,
@@ -49,3 +52,966 @@ fn test_lexer() {
     );
     assert_eq!(errors, vec![]);
 }
+
+#[test]
+fn test_colon() {
+    assert_eq!(
+        lex("where T: A + B"),
+        vec![
+            Token::Keyword(Keyword::Where),
+            Token::Identifier("T".to_string()),
+            Token::Colon,
+            Token::Identifier("A".to_string()),
+            Token::Operator(Operator::Add),
+            Token::Identifier("B".to_string()),
+        ],
+    );
+    assert_eq!(lex("a::b")[1], Token::Cast);
+}
+
+#[test]
+fn test_lexer_reuse() {
+    use terbium::grammar::ParseInterface;
+
+    let mut lexer = Lexer::new();
+    let mut out = Vec::new();
+
+    for code in ["let x = 1 + 2;", "func f(a) { return a ** 2; } // done"] {
+        lexer.lex_into(code, &mut out).unwrap();
+
+        let tokens = Vec::from_string(Source::default(), code.to_string()).unwrap();
+        assert_eq!(out, tokens);
+    }
+
+    assert!(lexer.lex_into("1 ` 2", &mut out).is_err());
+    assert!(out.is_empty());
+}
+
+#[test]
+fn test_operator_spacing() {
+    let mut lexer = Lexer::with_config(LexerConfig {
+        record_operator_spacing: true,
+        ..LexerConfig::default()
+    });
+    let mut out = Vec::new();
+    let mut spacing = |code: &str| {
+        lexer.lex_into(code, &mut out).unwrap();
+        lexer.operator_spacing().to_vec()
+    };
+    let spaced = |leading, trailing| Some(OperatorSpacing { leading, trailing });
+
+    assert_eq!(spacing("a - b"), [None, spaced(true, true), None]);
+    assert_eq!(spacing("a -b"), [None, spaced(true, false), None]);
+    assert_eq!(spacing("a-b"), [None, spaced(false, false), None]);
+    assert_eq!(spacing("-b"), [spaced(false, false), None]);
+    assert_eq!(spacing("f(-b)")[2], spaced(false, false));
+    assert_eq!(spacing("a\n-\tb")[1], spaced(true, true));
+
+    let mut lexer = Lexer::new();
+    lexer.lex_into("a - b", &mut out).unwrap();
+    assert!(lexer.operator_spacing().is_empty());
+}
+
+#[test]
+fn test_escape_spans() {
+    let mut lexer = Lexer::with_config(LexerConfig {
+        record_escapes: true,
+        ..LexerConfig::default()
+    });
+    let mut out = Vec::new();
+    let mut escapes = |code: &str| {
+        lexer.lex_into(code, &mut out).unwrap();
+        lexer
+            .escapes()
+            .iter()
+            .map(|escapes| {
+                escapes
+                    .iter()
+                    .map(|(kind, span)| (*kind, span.range))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        escapes(r#"x = "a\nb\x41";"#),
+        [
+            vec![],
+            vec![],
+            vec![(EscapeKind::Char, (6, 8)), (EscapeKind::Hex, (9, 13))],
+            vec![],
+        ],
+    );
+    assert_eq!(
+        escapes(r#"'é\U0001F600\''"#),
+        [vec![
+            (EscapeKind::Unicode, (2, 12)),
+            (EscapeKind::Char, (12, 14))
+        ]],
+    );
+    assert_eq!(
+        escapes(r#"$"{{{a}\t}}""#),
+        [vec![
+            (EscapeKind::Brace, (2, 4)),
+            (EscapeKind::Char, (7, 9)),
+            (EscapeKind::Brace, (9, 11)),
+        ]],
+    );
+    assert_eq!(
+        escapes(r#"u"%41%%""#),
+        [vec![
+            (EscapeKind::Percent, (2, 5)),
+            (EscapeKind::Percent, (5, 7))
+        ]],
+    );
+    assert_eq!(escapes(r#"r"\n""#), [vec![]]);
+
+    let mut lexer = Lexer::new();
+    lexer.lex_into(r#""\n""#, &mut out).unwrap();
+    assert!(lexer.escapes().is_empty());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_lex_parallel() {
+    use terbium::grammar::token::lex_parallel_with_chunk_size;
+
+    let code = r#"let a = "first line
+second // not a comment
+third";
+/* a block comment
+   "with a quote
+*/
+let b = 'it\'s
+fine';
+// a line comment with a "quote
+x >>
+    y;
+let c = 1 / 2 / 3;
+let p = r"C:\";
+let q = $r"{p}\";
+let s = "line1
+line2";
+let t = 1;
+"#;
+    let serial = get_lexer().parse(stream(code)).unwrap();
+
+    for chunk_size in [1, 2, 5, 10, 16, 1000] {
+        assert_eq!(
+            lex_parallel_with_chunk_size(code, chunk_size).unwrap(),
+            serial,
+            "chunk size {}",
+            chunk_size,
+        );
+    }
+
+    let code = code.repeat(50);
+    assert_eq!(
+        lex_parallel_with_chunk_size(&code, 64).unwrap(),
+        get_lexer().parse(stream(&code)).unwrap(),
+    );
+}
+
+#[test]
+fn test_lex_reader() {
+    use std::io::{Cursor, Read};
+
+    /// Hands out a single byte per read, splitting multi-byte characters across reads.
+    struct Trickle<R>(R);
+
+    impl<R: Read> Read for Trickle<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(&mut buf[..1])
+        }
+    }
+
+    let code = format!(
+        "let unicode = \"naïve\nstring\";\n/* ❤ */\nlet long = '{}';\nx >>\n  y; // done\n\
+         let p = r\"C:\\\";\nlet s = \"line1\nline2\";\nlet t = 1;\n",
+        "✓".repeat(10_000),
+    );
+    let byte_offset = |i: usize| code.char_indices().nth(i).map_or(code.len(), |(b, _)| b);
+    let expected = get_lexer()
+        .parse(stream(&code))
+        .unwrap()
+        .into_iter()
+        .map(|(token, span)| {
+            let range = byte_offset(span.start())..byte_offset(span.end());
+            (token, Span::from_range(Source::default(), range))
+        })
+        .collect::<Vec<_>>();
+
+    let tokens = lex_reader(Cursor::new(code.clone()))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(tokens, expected);
+
+    let tokens = lex_reader(Trickle(Cursor::new(code.clone())))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(tokens, expected);
+
+    // A single long line is scanned once rather than again after every read
+    let long = format!("let s = \"{}\";\n", "a".repeat(200_000));
+    assert_eq!(lex_reader(Trickle(Cursor::new(long))).count(), 5);
+
+    let mut tokens = lex_reader(Cursor::new(b"let x = 1;\nlet y \xFF".to_vec()));
+    assert_eq!(tokens.by_ref().take(5).count(), 5);
+    let rest = tokens.collect::<Vec<_>>();
+    assert!(matches!(rest.last(), Some(Err(e)) if e.message() == "source is not valid utf-8"));
+}
+
+#[cfg(feature = "float")]
+#[test]
+fn test_float_enabled() {
+    assert_eq!(
+        lex("1.5 .5 2"),
+        vec![
+            Token::Literal(Literal::Float("1.5".parse().unwrap())),
+            Token::Literal(Literal::Float(".5".parse().unwrap())),
+            Token::Literal(Literal::Integer(2)),
+        ],
+    );
+}
+
+#[cfg(not(feature = "float"))]
+#[test]
+fn test_float_disabled() {
+    use terbium::grammar::ParseInterface;
+
+    assert_eq!(
+        lex("12 0"),
+        vec![
+            Token::Literal(Literal::Integer(12)),
+            Token::Literal(Literal::Integer(0))
+        ]
+    );
+
+    let errors = Vec::from_string(Source::default(), "1 + 1.5".to_string()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "float literals are not supported");
+    assert_eq!(errors[0].span().range(), 4..7);
+}
+
+#[test]
+fn test_percent_strings() {
+    use terbium::grammar::ParseInterface;
+
+    let string = |s: &str| {
+        Token::Literal(Literal::String(StringLiteral::String(
+            s.to_string(),
+            QuoteStyle::Double,
+        )))
+    };
+
+    assert_eq!(lex(r#"u"%41""#), vec![string("A")]);
+    assert_eq!(lex(r#"u"100%% %C3%A9\n""#), vec![string(r"100% é\n")]);
+    assert_eq!(lex(r#"u "%41""#)[0], Token::Identifier("u".to_string()));
+
+    let errors = Vec::from_string(Source::default(), r#"u"%4G""#.to_string()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "invalid percent escape %4G");
+}
+
+#[test]
+fn test_byte_string_escapes() {
+    use terbium::grammar::ParseInterface;
+
+    assert_eq!(
+        lex(r#"~"\x41" "\u0041""#),
+        vec![
+            Token::Literal(Literal::String(StringLiteral::ByteString("A".to_string()))),
+            Token::Literal(Literal::String(StringLiteral::String(
+                "A".to_string(),
+                QuoteStyle::Double,
+            ))),
+        ],
+    );
+
+    let errors = Vec::from_string(Source::default(), r#"~"\u0041" 1"#.to_string()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message(),
+        r"unicode escape not allowed in byte string; use \xNN",
+    );
+    assert_eq!(errors[0].span().range(), 3..8);
+}
+
+#[test]
+fn test_raw_interpolated_strings() {
+    let interpolated = |s: &str| {
+        Token::Literal(Literal::String(StringLiteral::InterpolatedString(
+            s.to_string(),
+        )))
+    };
+
+    assert_eq!(
+        lex(r#"r$"C:\path\{name}""#),
+        vec![interpolated(r"C:\path\{name}")],
+    );
+    assert_eq!(
+        lex(r#"$r"\n{{not a hole}} {x}""#),
+        vec![interpolated(r"\n{{not a hole}} {x}")],
+    );
+    assert_eq!(
+        lex(r#"$"\n{{not a hole}} {x} \x7B""#),
+        vec![interpolated("\n{{not a hole}} {x} {{")],
+    );
+}
+
+#[test]
+fn test_interpolation_specs() {
+    let template = |code: &str| match lex(code).remove(0) {
+        Token::Literal(Literal::String(StringLiteral::InterpolatedString(s))) => s,
+        token => panic!("expected an interpolated string, found {:?}", token),
+    };
+    let tokens = |tokens: &[(Token, Span)]| tokens.iter().map(|t| t.0.clone()).collect::<Vec<_>>();
+
+    let parts = parse_interpolation(&template(r#"$"{value:>8.2}""#)).unwrap();
+    match &parts[..] {
+        [InterpolationPart::Hole { tokens: t, spec }] => {
+            assert_eq!(tokens(t), vec![Token::Identifier("value".to_string())]);
+            assert_eq!(spec.as_deref(), Some(">8.2"));
+        }
+        parts => panic!("unexpected parts {:?}", parts),
+    }
+
+    let parts = parse_interpolation(&template(r#"$"a {x::y + 1} b""#)).unwrap();
+    match &parts[..] {
+        [InterpolationPart::Literal(a), InterpolationPart::Hole {
+            tokens: t,
+            spec: None,
+        }, InterpolationPart::Literal(b)] => {
+            assert_eq!((a.as_str(), b.as_str()), ("a ", " b"));
+            assert_eq!(t.len(), 5);
+            assert_eq!(t[0].1.range(), 3..4);
+        }
+        parts => panic!("unexpected parts {:?}", parts),
+    }
+
+    assert_eq!(
+        parse_interpolation(&template(r#"$"{{not a hole}}""#)).unwrap(),
+        vec![InterpolationPart::Literal("{not a hole}".to_string())],
+    );
+
+    let parts = parse_interpolation(&template(r#"$"{x:{width}}""#)).unwrap();
+    assert!(
+        matches!(&parts[..], [InterpolationPart::Hole { spec: Some(spec), .. }] if spec == "{width}")
+    );
+}
+
+#[test]
+fn test_quote_style() {
+    assert_eq!(
+        lex(r#"'it\'s "quoted"' "it's \"quoted\"""#),
+        vec![
+            Token::Literal(Literal::String(StringLiteral::String(
+                r#"it's "quoted""#.to_string(),
+                QuoteStyle::Single,
+            ))),
+            Token::Literal(Literal::String(StringLiteral::String(
+                r#"it's "quoted""#.to_string(),
+                QuoteStyle::Double,
+            ))),
+        ],
+    );
+
+    for code in [r#"'it\'s "quoted"'"#, r#""it's \"quoted\"""#] {
+        let tokens = lex(code);
+        assert_eq!(tokens[0].to_string(), code);
+        assert_eq!(lex(&tokens[0].to_string()), tokens);
+    }
+}
+
+#[test]
+#[cfg(feature = "float")]
+fn test_token_ordering() {
+    use std::cmp::Ordering;
+    use std::collections::BTreeSet;
+
+    let mut tokens = lex("let b = 'x' + 2; let a = 1.5 + 10;");
+    let mut reversed = tokens.iter().rev().cloned().collect::<Vec<_>>();
+    tokens.sort();
+    reversed.sort();
+    assert_eq!(tokens, reversed);
+
+    let unique = tokens.iter().cloned().collect::<BTreeSet<_>>();
+    assert_eq!(unique.len(), 10);
+
+    assert_eq!(
+        Token::Identifier("a".to_string()).cmp(&Token::Identifier("a".to_string())),
+        Ordering::Equal,
+    );
+    assert!(Token::Identifier("a".to_string()) < Token::Identifier("b".to_string()));
+    assert!(Literal::Integer(2) < Literal::Integer(10));
+}
+
+#[test]
+fn test_consecutive_comments() {
+    assert_eq!(
+        lex("// one\n// two\n/* three */ x /* four */ // five\n"),
+        vec![Token::Identifier("x".to_string())],
+    );
+}
+
+#[test]
+fn test_ident_predicates() {
+    for (c, start, cont) in [
+        ('a', true, true),
+        ('Z', true, true),
+        ('_', true, true),
+        ('7', false, true),
+        ('-', false, false),
+        ('é', false, false),
+    ] {
+        assert_eq!(is_ident_start(c), start, "{:?}", c);
+        assert_eq!(is_ident_continue(c), cont, "{:?}", c);
+
+        // The lexer agrees on whether c starts or continues an identifier
+        let tokens = get_lexer().parse(stream(&format!("{} x{}", c, c)));
+        let tokens = tokens.unwrap_or_default();
+        assert_eq!(
+            tokens.first().map(|t| &t.0) == Some(&Token::Identifier(c.to_string())),
+            start,
+        );
+        assert_eq!(
+            tokens
+                .iter()
+                .any(|t| t.0 == Token::Identifier(format!("x{}", c))),
+            cont,
+        );
+    }
+}
+
+#[test]
+fn test_empty_program() {
+    for code in [
+        "",
+        "   \n\t  \r\n",
+        "// only a comment",
+        "/* one */\n// two\n  ",
+    ] {
+        let (tokens, errors) = get_lexer().parse_recovery(stream(code));
+
+        assert_eq!(tokens, Some(Vec::new()), "{:?}", code);
+        assert!(errors.is_empty(), "{:?}", code);
+    }
+}
+
+#[test]
+fn test_star_munching() {
+    assert_eq!(
+        lex("a * b ** c *= d **= e"),
+        vec![
+            Token::Identifier("a".to_string()),
+            Token::Operator(Operator::Mul),
+            Token::Identifier("b".to_string()),
+            Token::Operator(Operator::Pow),
+            Token::Identifier("c".to_string()),
+            Token::AugmentedAssign(Operator::Mul),
+            Token::Identifier("d".to_string()),
+            Token::AugmentedAssign(Operator::Pow),
+            Token::Identifier("e".to_string()),
+        ],
+    );
+    assert_eq!(
+        lex("***=**"),
+        vec![
+            Token::Operator(Operator::Pow),
+            Token::AugmentedAssign(Operator::Mul),
+            Token::Operator(Operator::Pow),
+        ],
+    );
+}
+
+#[test]
+fn test_symbol_table_munching() {
+    assert!(SYMBOLS.windows(2).all(|w| w[0].0.len() >= w[1].0.len()));
+
+    // Every symbol lexes as itself, so no shorter symbol shadows it
+    for (text, token) in SYMBOLS {
+        assert_eq!(&lex(text), std::slice::from_ref(token), "{:?}", text);
+        assert_eq!(&token.to_string(), text);
+    }
+}
+
+/// Maximal munch over the symbols as the lexer handled them before they were moved into
+/// [`SYMBOLS`], written out independently of the table.
+fn munch_symbols(mut code: &str) -> Vec<Token> {
+    use Operator::*;
+
+    let legacy = [
+        ("**=", Token::AugmentedAssign(Pow)),
+        ("<<=", Token::AugmentedAssign(BitLShift)),
+        (">>=", Token::AugmentedAssign(BitRShift)),
+        ("+=", Token::AugmentedAssign(Add)),
+        ("-=", Token::AugmentedAssign(Sub)),
+        ("*=", Token::AugmentedAssign(Mul)),
+        ("/=", Token::AugmentedAssign(Div)),
+        ("%=", Token::AugmentedAssign(Mod)),
+        ("|=", Token::AugmentedAssign(BitOr)),
+        ("^=", Token::AugmentedAssign(BitXor)),
+        ("&=", Token::AugmentedAssign(BitAnd)),
+        (",", Token::Comma),
+        (";", Token::Semicolon),
+        ("?", Token::Question),
+        ("@", Token::At),
+        ("::", Token::Cast),
+        (":", Token::Colon),
+        ("..", Token::Operator(Range)),
+        (".", Token::Dot),
+        ("+", Token::Operator(Add)),
+        ("-", Token::Operator(Sub)),
+        ("**", Token::Operator(Pow)),
+        ("*", Token::Operator(Mul)),
+        ("/", Token::Operator(Div)),
+        ("%", Token::Operator(Mod)),
+        ("==", Token::Operator(Eq)),
+        ("!=", Token::Operator(Ne)),
+        ("!", Token::Operator(Not)),
+        ("=", Token::Assign),
+        ("<=", Token::Operator(Le)),
+        (">=", Token::Operator(Ge)),
+        ("<<", Token::Operator(BitLShift)),
+        (">>", Token::Operator(BitRShift)),
+        ("<", Token::Operator(Lt)),
+        (">", Token::Operator(Gt)),
+        ("||", Token::Operator(Or)),
+        ("&&", Token::Operator(And)),
+        ("|", Token::Operator(BitOr)),
+        ("^", Token::Operator(BitXor)),
+        ("&", Token::Operator(BitAnd)),
+        ("~", Token::Operator(BitNot)),
+        ("(", Token::StartBracket(Bracket::Paren)),
+        ("[", Token::StartBracket(Bracket::Bracket)),
+        ("{", Token::StartBracket(Bracket::Brace)),
+        (")", Token::EndBracket(Bracket::Paren)),
+        ("]", Token::EndBracket(Bracket::Bracket)),
+        ("}", Token::EndBracket(Bracket::Brace)),
+    ];
+    let mut tokens = Vec::new();
+
+    while !code.is_empty() {
+        let (text, token) = legacy
+            .iter()
+            .filter(|(text, _)| code.starts_with(text))
+            .max_by_key(|(text, _)| text.len())
+            .unwrap();
+
+        tokens.push(token.clone());
+        code = code[text.len()..].trim_start();
+    }
+    tokens
+}
+
+#[test]
+fn test_symbol_table_matches_legacy() {
+    let texts = SYMBOLS.iter().map(|(text, _)| *text).collect::<Vec<_>>();
+    let mut corpus = Vec::new();
+
+    for a in &texts {
+        for b in &texts {
+            for c in ["", " "] {
+                let code = format!("{}{}{}", a, c, b);
+                // These would start comments instead
+                if !code.contains("//") && !code.contains("/*") {
+                    corpus.push(code);
+                }
+            }
+        }
+    }
+    let corpus = corpus.join(" ");
+
+    assert_eq!(lex(&corpus), munch_symbols(&corpus));
+}
+
+#[test]
+#[cfg(feature = "float")]
+fn test_normalized_float() {
+    let normalized = lex("1.0 1.00 1. 0.10 .1 123456789.125000")
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(literal) => literal.normalized_float().unwrap(),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        normalized,
+        ["1.0", "1.0", "1.0", "0.1", "0.1", "123456789.125"],
+    );
+    for text in &normalized {
+        let value = text.parse::<f64>().unwrap();
+        assert_eq!(Literal::Float(text.parse().unwrap()).as_f64(), Some(value));
+        assert_eq!(&format!("{:?}", value), text);
+    }
+
+    // Only the normalized form changes, not the literal
+    assert_eq!(
+        lex("1.00"),
+        [Token::Literal(Literal::Float("1.00".parse().unwrap()))]
+    );
+    assert_eq!(Literal::Integer(1).normalized_float(), None);
+}
+
+#[test]
+#[cfg(feature = "float")]
+fn test_float_range_ambiguity() {
+    let float = |f: &str| Token::Literal(Literal::Float(f.parse().unwrap()));
+    let int = |i| Token::Literal(Literal::Integer(i));
+    let range = Token::Operator(Operator::Range);
+
+    assert_eq!(lex("1..2"), [int(1), range.clone(), int(2)]);
+    assert_eq!(lex("1.2"), [float("1.2")]);
+    assert_eq!(lex("1."), [float("1.")]);
+    // The dot after `1` is followed by another dot, so it is not a decimal point
+    assert_eq!(lex("1...2"), [int(1), range.clone(), float(".2")]);
+    assert_eq!(lex("1..=2"), [int(1), range, Token::Assign, int(2)]);
+}
+
+#[test]
+fn test_dot_range_munching() {
+    let ident = |name: &str| Token::Identifier(name.to_string());
+    let range = Token::Operator(Operator::Range);
+
+    assert_eq!(lex("a..b"), [ident("a"), range.clone(), ident("b")]);
+    assert_eq!(lex("a.b"), [ident("a"), Token::Dot, ident("b")]);
+    assert_eq!(
+        lex("a...b"),
+        [ident("a"), range.clone(), Token::Dot, ident("b")]
+    );
+    assert_eq!(lex("..."), [range.clone(), Token::Dot]);
+    assert_eq!(lex("...."), [range.clone(), range.clone()]);
+    assert_eq!(lex(". .."), [Token::Dot, range.clone()]);
+    assert_eq!(lex("a.. .b"), [ident("a"), range, Token::Dot, ident("b")]);
+}
+
+#[test]
+#[cfg(feature = "float")]
+fn test_trailing_dot_float() {
+    let float = |f: &str| Token::Literal(Literal::Float(f.parse().unwrap()));
+
+    // A trailing dot makes a float, which keeps its source text
+    assert_eq!(lex("1."), [float("1.")]);
+    assert_eq!(
+        lex("1. + 2"),
+        [
+            float("1."),
+            Token::Operator(Operator::Add),
+            Token::Literal(Literal::Integer(2)),
+        ],
+    );
+    assert_eq!(lex("(1.)")[1], float("1."));
+    assert_eq!(lex("1.0"), [float("1.0")]);
+
+    // unless an identifier follows, which makes it an attribute instead
+    assert_eq!(
+        lex("1.foo"),
+        [
+            Token::Literal(Literal::Integer(1)),
+            Token::Dot,
+            Token::Identifier("foo".to_string()),
+        ],
+    );
+    assert_eq!(lex("1._x")[1], Token::Dot);
+}
+
+fn assert_round_trip(literal: &StringLiteral) {
+    let token = Token::Literal(Literal::String(literal.clone()));
+
+    assert_eq!(
+        lex(&literal.to_string()),
+        [token],
+        "{:?} displayed as {}",
+        literal,
+        literal,
+    );
+}
+
+#[test]
+fn test_string_display_round_trip() {
+    let code = r#"
+        "line\nbreak" 'it\'s "quoted"' "tab\tbell\x07\\" r"C:\path\n" ~"bytes\x00\xff\n"
+        $"{{x}} = {x:>8} \u00e9\n" r$"\d{n}" "\U0001F600" u"%41%25"
+    "#;
+    let literals = lex(code)
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(Literal::String(literal)) => literal,
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(literals.len(), 9);
+    assert_eq!(literals[0].to_string(), r#""line\nbreak""#);
+    assert_eq!(literals[1].to_string(), r#"'it\'s "quoted"'"#);
+    assert_eq!(
+        literals[3],
+        StringLiteral::RawString(r"C:\path\n".to_string())
+    );
+    assert_eq!(literals[3].to_string(), r#"r"C:\path\n""#);
+    assert_eq!(literals[4].to_string(), r#"~"bytes\x00ÿ\n""#);
+
+    for literal in &literals {
+        assert_round_trip(literal);
+    }
+
+    // Every ASCII character and a few others, in every kind of string which escapes
+    let text = (0..128_u8)
+        .map(char::from)
+        .chain(['é', '\u{85}', '\u{200B}', '🦀'])
+        .collect::<String>();
+    for literal in [
+        StringLiteral::String(text.clone(), QuoteStyle::Double),
+        StringLiteral::String(text.clone(), QuoteStyle::Single),
+        StringLiteral::ByteString(text.clone()),
+        StringLiteral::InterpolatedString(text.replace('{', "{{").replace('}', "}}")),
+    ] {
+        assert_round_trip(&literal);
+    }
+}
+
+#[test]
+fn test_integer_display_round_trip() {
+    for code in ["0", "255", "170141183460469231731687303715884105727"] {
+        let tokens = lex(code);
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].to_string(), code);
+    }
+
+    // There are no radix prefixes, so this is the integer `0` followed by an identifier
+    assert_eq!(
+        lex("0xFF"),
+        vec![
+            Token::Literal(Literal::Integer(0)),
+            Token::Identifier("xFF".to_string()),
+        ],
+    );
+}
+
+#[test]
+fn test_literal_constructors() {
+    let relex = |literal: &Literal| {
+        let config = LexerConfig {
+            float_constants: true,
+            ..LexerConfig::default()
+        };
+        lex_with_config(&literal.to_string(), config)
+    };
+    let assert_relexes = |literal: Literal| {
+        assert_eq!(
+            relex(&literal),
+            [Token::Literal(literal.clone())],
+            "{}",
+            literal
+        );
+    };
+
+    assert_relexes(Literal::int(0).unwrap());
+    assert_relexes(Literal::int(i128::MAX as u128).unwrap());
+    assert_eq!(Literal::int(i128::MAX as u128 + 1), None);
+
+    assert_relexes(Literal::string("say \"hi\"\n"));
+    assert_relexes(Literal::String(StringLiteral::raw(r"C:\path").unwrap()));
+    assert_eq!(StringLiteral::raw("a\"b"), None);
+
+    assert_eq!(Literal::float_from_f64(-1.5), None);
+    assert_eq!(Literal::float_from_f64(-0.0), None);
+    assert_eq!(Literal::float_from_f64(2.0).unwrap().to_string(), "2.0");
+    assert_eq!(
+        Literal::float_from_f64(f64::INFINITY).unwrap().to_string(),
+        "inf"
+    );
+    assert_eq!(
+        Literal::float_from_f64(f64::NAN).unwrap().to_string(),
+        "nan"
+    );
+
+    #[cfg(feature = "float")]
+    for value in [0.0, 0.1, 2.0, 1e-7, 1e20, f64::MAX, f64::INFINITY] {
+        let literal = Literal::float_from_f64(value).unwrap();
+
+        assert_relexes(literal.clone());
+        assert_eq!(literal.as_f64(), Some(value));
+    }
+    #[cfg(feature = "float")]
+    assert_relexes(Literal::float_from_f64(f64::NAN).unwrap());
+}
+
+#[test]
+fn test_non_ascii_lexes_like_ascii() {
+    let code = |text: &str| format!("let s = \"{}\"; // {}\nf(s) /* {} */ + 1", text, text, text);
+    let ascii = lex_bytes(code("Hello!!").as_bytes()).unwrap();
+    let unicode = lex_bytes(code("Grüß 👋!").as_bytes()).unwrap();
+
+    // Only the string differs, and both texts are as many chars long
+    assert_eq!(ascii.len(), unicode.len());
+    for ((a, a_span), (u, u_span)) in ascii.iter().zip(&unicode) {
+        assert_eq!(a_span, u_span);
+        assert_eq!(a.kind(), u.kind());
+        if !matches!(a, Token::Literal(_)) {
+            assert_eq!(a, u);
+        }
+    }
+    assert_eq!(unicode[3].0.to_string(), "\"Grüß 👋!\"");
+}
+
+#[test]
+fn test_lex_bytes() {
+    let tokens = lex_bytes("let s = \"héllo\";".as_bytes()).unwrap();
+    assert_eq!(tokens.len(), 5);
+    // Spans are still char offsets
+    assert_eq!(tokens[4].1.range(), 15..16);
+
+    // A two-byte character cut off after its lead byte
+    let errors = lex_bytes(b"let s = \"h\xC3").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message(),
+        "source ends in the middle of a utf-8 sequence"
+    );
+    assert_eq!(errors[0].span().range(), 10..11);
+
+    // 0xFF can never appear in UTF-8
+    let errors = lex_bytes(b"x = 1;\n\xFFy = 2;").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message(), "source is not valid utf-8");
+    assert_eq!(errors[0].span().range(), 7..8);
+}
+
+#[test]
+fn test_fixed_token_spellings() {
+    let spellings = fixed_token_spellings();
+
+    assert_eq!(spellings.len(), KEYWORDS.len() + SYMBOLS.len());
+    assert!(spellings.contains(&(Token::Keyword(Keyword::SelfKw), "self")));
+    assert!(spellings.contains(&(Token::AugmentedAssign(Operator::Pow), "**=")));
+    assert!(!spellings
+        .iter()
+        .any(|(token, _)| matches!(token, Token::Identifier(_) | Token::Literal(_))));
+
+    for (token, text) in spellings {
+        let lexed = lex(&format!("{} ", text));
+
+        assert_eq!(lexed.len(), 1, "{:?}", text);
+        assert_eq!(lexed[0].kind(), token.kind(), "{:?}", text);
+        assert_eq!(&lexed[0], token);
+        assert_eq!(&token.to_string(), text);
+    }
+}
+
+#[test]
+#[cfg(feature = "float")]
+fn test_float_value() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let floats = lex("2.75 .5 2. 1.0 1.0 1.00")
+        .into_iter()
+        .map(|token| match token {
+            Token::Literal(Literal::Float(float)) => float,
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>();
+
+    for float in &floats {
+        assert_eq!(float.value, float.raw.parse::<f64>().unwrap());
+    }
+    assert_eq!(floats[0].value, 2.75);
+
+    let hash = |float: &Float| {
+        let mut hasher = DefaultHasher::new();
+        float.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    // Identical text compares and hashes equal, while equal values spelled differently don't
+    assert_eq!(floats[3], floats[4]);
+    assert_eq!(hash(&floats[3]), hash(&floats[4]));
+    assert_ne!(floats[4], floats[5]);
+    assert_eq!(floats[4].value, floats[5].value);
+}
+
+#[test]
+fn test_bracket_chars() {
+    let brackets = [Bracket::Paren, Bracket::Bracket, Bracket::Brace];
+
+    for (bracket, open, close) in [
+        (Bracket::Paren, '(', ')'),
+        (Bracket::Bracket, '[', ']'),
+        (Bracket::Brace, '{', '}'),
+    ] {
+        assert_eq!(bracket.open_char(), open);
+        assert_eq!(bracket.close_char(), close);
+        assert_eq!(bracket.to_string(), open.to_string());
+        assert_eq!(Token::StartBracket(bracket).to_string(), open.to_string());
+        assert_eq!(Token::EndBracket(bracket).to_string(), close.to_string());
+
+        for other in brackets {
+            assert_eq!(Bracket::matches(bracket, other), bracket == other);
+        }
+    }
+}
+
+/// Parses all of `code` with `parser`, or returns `None` if it fails or stops early.
+fn parse_all<T>(parser: impl ChumskyParser<char, T, Error = Error>, code: &str) -> Option<T> {
+    parser
+        .map_with_span(|value, span: Span| (value, span.end()))
+        .parse(stream(code))
+        .ok()
+        .filter(|(_, end)| *end == code.chars().count())
+        .map(|(value, _)| value)
+}
+
+#[test]
+fn test_combinators() {
+    let config = LexerConfig::default();
+
+    assert_eq!(
+        parse_all(integer(), "42"),
+        Some(Token::Literal(Literal::Integer(42)))
+    );
+    assert_eq!(parse_all(integer(), "4.2"), None);
+    #[cfg(feature = "float")]
+    {
+        assert_eq!(parse_all(float(), ".5"), lex(".5").pop());
+        assert_eq!(parse_all(float(), "5"), None);
+    }
+    assert_eq!(parse_all(string(), "r\"a\\b\""), lex("r\"a\\b\"").pop());
+    assert_eq!(parse_all(string(), "$\"{x}\""), lex("$\"{x}\"").pop());
+    assert_eq!(parse_all(string(), "a"), None);
+    assert_eq!(
+        parse_all(ident_or_keyword(&config), "func"),
+        Some(Token::Keyword(Keyword::Func))
+    );
+    assert_eq!(
+        parse_all(ident_or_keyword(&config), "r#func"),
+        Some(Token::Identifier("func".to_string()))
+    );
+    assert_eq!(parse_all(ident_or_keyword(&config), "1"), None);
+    assert_eq!(
+        parse_all(operator_symbol(&config), "**="),
+        Some(Token::AugmentedAssign(Operator::Pow))
+    );
+    assert_eq!(parse_all(operator_symbol(&config), "a"), None);
+    assert_eq!(parse_all(comment(&config), "// a"), Some(()));
+    assert_eq!(parse_all(comment(&config), "/* a */"), Some(()));
+    assert_eq!(parse_all(comment(&config), "a"), None);
+
+    // A dialect which lexes `//` as floor division and has neither strings nor floats
+    let config = LexerConfig {
+        slash_slash: SlashSlash::FloorDiv,
+        ..LexerConfig::default()
+    };
+    let dialect = operator_symbol(&config)
+        .or(ident_or_keyword(&config))
+        .or(integer())
+        .repeated();
+
+    assert_eq!(
+        parse_all(dialect.clone(), "a//25"),
+        Some(lex_with_config("a//25", config))
+    );
+    assert_eq!(parse_all(dialect, "\"a\""), None);
+}
@@ -0,0 +1,72 @@
+use terbium::grammar::lint::{chained_comparisons, redundant_parens};
+use terbium::grammar::{Body, ErrorCode, ParseInterface, Severity, Source};
+
+fn redundant(code: &str) -> Vec<(usize, usize)> {
+    let body = Body::from_string(Source::default(), code.to_string()).unwrap();
+
+    redundant_parens(&body)
+        .into_iter()
+        .map(|warning| {
+            assert!(warning.is_warning());
+            assert_eq!(warning.code, ErrorCode::RedundantParens);
+            warning.span.range
+        })
+        .collect()
+}
+
+#[test]
+fn test_redundant_parens() {
+    // Both the outer pair and the inner one
+    assert_eq!(redundant("((a));"), [(0, 5), (1, 4)]);
+    assert_eq!(redundant("(a) + b;"), [(0, 3)]);
+    assert_eq!(redundant("let x = (a * b) + c;"), [(8, 15)]);
+    assert_eq!(redundant("(a - b) - c;"), [(0, 7)]);
+    assert_eq!(redundant("a ** (b ** c);"), [(5, 13)]);
+    assert_eq!(redundant("f((a + b));"), [(2, 9)]);
+    assert_eq!(redundant("if (a) { (b); }"), [(3, 6), (9, 12)]);
+    assert_eq!(redundant("(a | b)..c;"), [(0, 7)]);
+
+    assert!(redundant("(a + b) * c;").is_empty());
+    assert!(redundant("a - (b - c);").is_empty());
+    assert!(redundant("(a ** b) ** c;").is_empty());
+    assert!(redundant("-(a + b);").is_empty());
+    assert!(redundant("&(&a);").is_empty());
+    assert!(redundant("(a + b).c;").is_empty());
+    assert!(redundant("f(a + b);").is_empty());
+    assert!(redundant("(a..b)..;").is_empty());
+    assert!(redundant("(a < b) == c;").is_empty());
+}
+
+#[test]
+fn test_chained_comparisons() {
+    let chained = |code: &str| {
+        let body = Body::from_string(Source::default(), code.to_string()).unwrap();
+
+        chained_comparisons(&body, Severity::Warning)
+            .into_iter()
+            .map(|warning| {
+                assert_eq!(warning.code, ErrorCode::ChainedComparison);
+                (warning.span.range, warning.message)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        chained("a < b < c;"),
+        [(
+            (0, 9),
+            "comparison operators '<' and '<' are chained".to_string()
+        )],
+    );
+    assert_eq!(chained("x = a == b != c;")[0].0, (4, 15));
+    // A longer chain is still reported once
+    assert_eq!(chained("a < b <= c < d;").len(), 1);
+    assert_eq!(chained("f(a > b > c) && d < e < f;").len(), 2);
+
+    assert!(chained("a < b && b < c;").is_empty());
+    assert!(chained("(a < b) == c;").is_empty());
+    assert!(chained("a < b + c;").is_empty());
+
+    let body = Body::from_string(Source::default(), "a < b < c;".to_string()).unwrap();
+    assert!(!chained_comparisons(&body, Severity::Error)[0].is_warning());
+}
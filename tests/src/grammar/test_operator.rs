@@ -0,0 +1,173 @@
+use terbium::grammar::token::{Arity, Associativity};
+use terbium::grammar::Operator;
+
+#[test]
+fn test_negate() {
+    let pairs = [
+        (Operator::Eq, Operator::Ne),
+        (Operator::Ne, Operator::Eq),
+        (Operator::Lt, Operator::Ge),
+        (Operator::Le, Operator::Gt),
+        (Operator::Gt, Operator::Le),
+        (Operator::Ge, Operator::Lt),
+    ];
+
+    for (op, negated) in pairs {
+        assert_eq!(op.negate(), Some(negated));
+        assert_eq!(negated.negate(), Some(op));
+    }
+
+    for op in [
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+        Operator::Pow,
+        Operator::And,
+        Operator::Not,
+        Operator::BitAnd,
+    ] {
+        assert_eq!(op.negate(), None);
+    }
+}
+
+#[test]
+fn test_flip_operands() {
+    assert_eq!(Operator::Lt.flip_operands(), Some(Operator::Gt));
+    assert_eq!(Operator::Le.flip_operands(), Some(Operator::Ge));
+    assert_eq!(Operator::Gt.flip_operands(), Some(Operator::Lt));
+    assert_eq!(Operator::Ge.flip_operands(), Some(Operator::Le));
+
+    assert_eq!(Operator::Eq.flip_operands(), Some(Operator::Eq));
+    assert_eq!(Operator::Ne.flip_operands(), Some(Operator::Ne));
+
+    assert_eq!(Operator::BitXor.flip_operands(), Some(Operator::BitXor));
+    for op in [
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+        Operator::Mod,
+        Operator::Pow,
+        Operator::BitLShift,
+        Operator::Range,
+    ] {
+        assert_eq!(op.flip_operands(), None);
+    }
+}
+
+#[test]
+fn test_assignments() {
+    use super::lex;
+
+    let tokens = lex("a = 1; b += 2; c <<= 3; d <= 4; e >>= f ** g **= h;");
+    let assignments = tokens
+        .iter()
+        .filter_map(|token| token.as_assignment())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        assignments,
+        vec![
+            None,
+            Some(Operator::Add),
+            Some(Operator::BitLShift),
+            Some(Operator::BitRShift),
+            Some(Operator::Pow),
+        ],
+    );
+    assert_eq!(tokens[5].to_string(), "+=");
+
+    assert!(Operator::BitLShift.supports_assignment());
+    assert!(!Operator::Lt.supports_assignment());
+}
+
+#[test]
+fn test_compound_base() {
+    use super::lex;
+    use terbium::grammar::token::SYMBOLS;
+    use terbium::grammar::Token;
+
+    let compound = SYMBOLS
+        .iter()
+        .filter(|(_, token)| matches!(token, Token::AugmentedAssign(_)))
+        .collect::<Vec<_>>();
+    assert_eq!(compound.len(), 11);
+
+    // Each compound assignment applies the operator spelled without its `=`
+    for (text, token) in compound {
+        let base = lex(text.strip_suffix('=').unwrap());
+        assert_eq!(
+            token.compound_base(),
+            Some(match &base[..] {
+                [Token::Operator(op)] => *op,
+                _ => unreachable!(),
+            }),
+            "{:?}",
+            text
+        );
+    }
+
+    assert_eq!(lex("<<=")[0].compound_base(), Some(Operator::BitLShift));
+    assert_eq!(Token::Assign.compound_base(), None);
+    assert_eq!(lex("==")[0].compound_base(), None);
+    assert_eq!(lex("<=")[0].compound_base(), None);
+}
+
+#[test]
+fn test_associativity() {
+    assert_eq!(Operator::Pow.associativity(), Associativity::Right);
+    assert_eq!(Operator::Sub.associativity(), Associativity::Left);
+    assert_eq!(Operator::Div.associativity(), Associativity::Left);
+    assert_eq!(
+        Operator::Range.associativity(),
+        Associativity::NonAssociative
+    );
+}
+
+#[test]
+fn test_arity() {
+    assert_eq!(Operator::Sub.arity(), Arity::Both);
+    assert_eq!(Operator::Add.arity(), Arity::Both);
+    assert_eq!(Operator::Mul.arity(), Arity::Binary);
+    assert_eq!(Operator::Range.arity(), Arity::Binary);
+    assert_eq!(Operator::BitNot.arity(), Arity::Unary);
+    assert_eq!(Operator::RefMut.arity(), Arity::Unary);
+
+    for op in [
+        Operator::Add,
+        Operator::Sub,
+        Operator::Mul,
+        Operator::Div,
+        Operator::FloorDiv,
+        Operator::Mod,
+        Operator::Pow,
+        Operator::Eq,
+        Operator::Ne,
+        Operator::Lt,
+        Operator::Le,
+        Operator::Gt,
+        Operator::Ge,
+        Operator::Or,
+        Operator::And,
+        Operator::Not,
+        Operator::BitOr,
+        Operator::BitXor,
+        Operator::BitAnd,
+        Operator::BitNot,
+        Operator::BitLShift,
+        Operator::BitRShift,
+        Operator::Range,
+        Operator::RefMut,
+    ] {
+        assert!(op.supports_unary() || op.supports_binary());
+        assert_eq!(
+            matches!(op.arity(), Arity::Unary | Arity::Both),
+            op.supports_unary()
+        );
+        assert_eq!(
+            matches!(op.arity(), Arity::Binary | Arity::Both),
+            op.supports_binary()
+        );
+    }
+}
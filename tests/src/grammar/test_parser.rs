@@ -0,0 +1,505 @@
+use terbium::grammar::ast::{Bound, SpannedExpr, DEFAULT_MAX_DEPTH};
+use terbium::grammar::{Body, Expr, Operator, ParseInterface, Source, Span, Spanned, TypeExpr};
+
+fn parse(code: &str) -> Expr {
+    Expr::from_string(Source::default(), code.to_string()).unwrap_or_else(|e| {
+        panic!("parse error: {:?}", e);
+    })
+}
+
+fn spanned<T>(node: T) -> Spanned<T> {
+    Spanned::new(node, Span::default())
+}
+
+fn ident(name: &str) -> SpannedExpr {
+    spanned(Expr::Ident(name.to_string()))
+}
+
+#[test]
+fn test_nesting_depth() {
+    let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    let grouped =
+        |depth: usize| (0..depth).fold(Expr::Integer(1), |expr, _| Expr::Group(spanned(expr)));
+
+    assert_eq!(
+        Expr::from_string(Source::default(), nested(DEFAULT_MAX_DEPTH)),
+        Ok(grouped(DEFAULT_MAX_DEPTH)),
+    );
+
+    let errors = Expr::from_string(Source::default(), nested(10_000)).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "expression nesting too deep");
+    assert_eq!(
+        errors[0].span.range(),
+        DEFAULT_MAX_DEPTH..DEFAULT_MAX_DEPTH + 1
+    );
+
+    let tokens = Vec::from_string(Source::default(), nested(4)).unwrap();
+    assert!(Expr::parse_with_max_depth(tokens.clone(), 3).is_err());
+    assert_eq!(Expr::parse_with_max_depth(tokens, 4), Ok(grouped(4)));
+
+    // Operator chains nest as well
+    let too_deep = |code: String| {
+        Body::from_string(Source::default(), code)
+            .unwrap_err()
+            .iter()
+            .any(|e| e.message == "expression nesting too deep")
+    };
+    assert!(too_deep("-".repeat(30_000) + "a;"));
+    assert!(too_deep("a + ".repeat(30_000) + "a;"));
+    assert!(too_deep(format!(
+        "({}a);",
+        "a + ".repeat(DEFAULT_MAX_DEPTH)
+    )));
+    assert!(Body::from_string(
+        Source::default(),
+        "f(a + b, c + d);".repeat(DEFAULT_MAX_DEPTH)
+    )
+    .is_ok());
+
+    // Blocks end their statements, so operators before them don't add up
+    assert!(Body::from_string(
+        Source::default(),
+        "if a == b { c; }\nwhile i < n { i = i + 1; }\n".repeat(300)
+    )
+    .is_ok());
+
+    let tokens = Vec::from_string(Source::default(), "-(-a) - b".to_string()).unwrap();
+    assert!(Expr::parse_with_max_depth(tokens.clone(), 2).is_err());
+    assert!(Expr::parse_with_max_depth(tokens, 3).is_ok());
+
+    // The stack grows as needed, even on a small one
+    let parsed = std::thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(move || Expr::from_string(Source::default(), nested(DEFAULT_MAX_DEPTH)))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert_eq!(parsed, Ok(grouped(DEFAULT_MAX_DEPTH)));
+}
+
+#[test]
+fn test_address_of() {
+    assert_eq!(
+        parse("&x"),
+        Expr::UnaryExpr {
+            operator: spanned(Operator::BitAnd),
+            value: ident("x"),
+        },
+    );
+    assert_eq!(
+        parse("a & b"),
+        Expr::BinaryExpr {
+            operator: spanned(Operator::BitAnd),
+            lhs: ident("a"),
+            rhs: ident("b"),
+        },
+    );
+    assert_eq!(
+        parse("a & &b"),
+        Expr::BinaryExpr {
+            operator: spanned(Operator::BitAnd),
+            lhs: ident("a"),
+            rhs: spanned(Expr::UnaryExpr {
+                operator: spanned(Operator::BitAnd),
+                value: ident("b"),
+            }),
+        },
+    );
+    assert!(Operator::BitAnd.supports_unary() && Operator::BitAnd.supports_binary());
+}
+
+#[test]
+fn test_mutable_reference() {
+    assert_eq!(
+        parse("&mut x"),
+        Expr::UnaryExpr {
+            operator: spanned(Operator::RefMut),
+            value: ident("x"),
+        },
+    );
+    assert_ne!(parse("&mut x"), parse("&x"));
+    assert!(Operator::RefMut.supports_unary() && !Operator::RefMut.supports_binary());
+
+    assert!(Expr::from_string(Source::default(), "a & mut".to_string()).is_err());
+    assert!(Expr::from_string(Source::default(), "mut x".to_string()).is_err());
+}
+
+#[test]
+fn test_associativity() {
+    let int = |i: u128| spanned(Expr::Integer(i));
+    let binary = |operator, lhs, rhs| {
+        spanned(Expr::BinaryExpr {
+            operator: spanned(operator),
+            lhs,
+            rhs,
+        })
+    };
+
+    assert_eq!(
+        parse("2 ** 3 ** 2"),
+        binary(Operator::Pow, int(2), binary(Operator::Pow, int(3), int(2))).into_node(),
+    );
+    assert_eq!(
+        parse("2 - 3 - 2"),
+        binary(Operator::Sub, binary(Operator::Sub, int(2), int(3)), int(2)).into_node(),
+    );
+}
+
+#[test]
+fn test_bounds() {
+    assert_eq!(
+        Bound::from_string(Source::default(), "T: A + mod.B".to_string()),
+        Ok(Bound {
+            param: "T".to_string(),
+            bounds: vec![
+                TypeExpr::Ident("A".to_string()),
+                TypeExpr::Attr(
+                    Box::new(TypeExpr::Ident("mod".to_string())),
+                    "B".to_string()
+                ),
+            ],
+        }),
+    );
+    assert_eq!(
+        Bound::from_string(Source::default(), "T".to_string()),
+        Ok(Bound {
+            param: "T".to_string(),
+            bounds: vec![],
+        }),
+    );
+    assert!(Bound::from_string(Source::default(), "T:".to_string()).is_err());
+    assert!(Bound::from_string(Source::default(), "T: A +".to_string()).is_err());
+}
+
+#[test]
+fn test_integer_attribute() {
+    assert_eq!(
+        parse("1.method()"),
+        Expr::Call {
+            value: spanned(Expr::Attr(spanned(Expr::Integer(1)), "method".to_string())),
+            args: Vec::new(),
+            kwargs: Vec::new(),
+        },
+    );
+}
+
+#[test]
+fn test_soft_keywords_as_names() {
+    use terbium::grammar::ast::Target;
+    use terbium::grammar::Node;
+
+    let body = |code: &str| Body::from_string(Source::default(), code.to_string());
+
+    let Body(nodes, _) = body("let where = 1; where;").unwrap();
+    assert_eq!(
+        nodes[0].node(),
+        &Node::Declare {
+            targets: vec![spanned(Target::Ident("where".to_string()))],
+            value: spanned(Expr::Integer(1)),
+            r#mut: false,
+            r#const: false,
+        },
+    );
+    assert_eq!(nodes[1].node(), &Node::Expr(ident("where")));
+
+    assert!(body("func match(type) { type }").is_ok());
+    assert!(body("require from, export;").is_ok());
+    assert_eq!(parse("x.is"), Expr::Attr(ident("x"), "is".to_string()));
+
+    assert!(body("let if = 1;").is_err());
+    assert!(body("let with = 1;").is_err());
+    assert!(body("func let() {}").is_err());
+}
+
+#[test]
+fn test_keyword_as_identifier() {
+    use terbium::grammar::{ErrorCode, HintAction};
+
+    let errors = Body::from_string(Source::default(), "let func = 1;".to_string()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::KeywordAsIdentifier);
+    assert_eq!(errors[0].span.range, (4, 8));
+    assert_eq!(
+        errors[0].message,
+        "'func' is a keyword and can't be used as an identifier"
+    );
+
+    let hint = errors[0].hint.as_ref().unwrap();
+    assert_eq!(hint.message, "use 'r#func' to use it as an identifier");
+    assert_eq!(hint.action, HintAction::Replace("r#func".to_string()));
+
+    // Following the suggestion works
+    assert!(Body::from_string(Source::default(), "let r#func = 1;".to_string()).is_ok());
+}
+
+#[test]
+fn test_decorators() {
+    use terbium::grammar::ast::{parse_decorator, Decorator};
+    use terbium::grammar::cursor::Cursor;
+    use terbium::grammar::token::{lex, Bracket, Keyword, Literal, QuoteStyle, StringLiteral};
+    use terbium::grammar::Token;
+
+    let tokens = lex("@cached func f() {}").unwrap();
+    assert_eq!(tokens[0].0, Token::At);
+    assert_eq!(tokens[1].0, Token::Identifier("cached".to_string()));
+
+    let mut cursor = Cursor::new(tokens.as_slice());
+    let decorator = parse_decorator(&mut cursor).unwrap();
+    assert_eq!(
+        decorator.node(),
+        &Decorator {
+            name: "cached".to_string(),
+            args: None,
+        },
+    );
+    assert_eq!(decorator.span().range(), 0..7);
+    assert_eq!(cursor.peek().unwrap().0, Token::Keyword(Keyword::Func));
+
+    let tokens = lex("@route(\"/x\")").unwrap();
+    assert_eq!(
+        tokens.iter_tokens().cloned().collect::<Vec<_>>(),
+        vec![
+            Token::At,
+            Token::Identifier("route".to_string()),
+            Token::StartBracket(Bracket::Paren),
+            Token::Literal(Literal::String(StringLiteral::String(
+                "/x".to_string(),
+                QuoteStyle::Double
+            ))),
+            Token::EndBracket(Bracket::Paren),
+        ],
+    );
+
+    let decorator = parse_decorator(&mut Cursor::new(tokens.as_slice())).unwrap();
+    assert_eq!(
+        decorator.node(),
+        &Decorator {
+            name: "route".to_string(),
+            args: Some(vec![spanned(Expr::String("/x".to_string()))]),
+        },
+    );
+    assert_eq!(decorator.span().range(), 0..12);
+
+    let decorator =
+        |code: &str| parse_decorator(&mut Cursor::new(lex(code).unwrap().as_slice())).ok();
+    assert_eq!(
+        decorator("@retry(1 + 2, [3, 4],)")
+            .unwrap()
+            .node()
+            .args
+            .as_ref()
+            .map(Vec::len),
+        Some(2),
+    );
+    assert_eq!(decorator("@where()").unwrap().node().args, Some(Vec::new()));
+
+    assert!(decorator("cached").is_none());
+    assert!(decorator("@if").is_none());
+    assert!(decorator("@route(\"/x\"").is_none());
+    assert!(decorator("@route(,)").is_none());
+
+    let tokens = lex("@1").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+    assert!(parse_decorator(&mut cursor).is_err());
+    assert_eq!(cursor.position(), 0);
+}
+
+#[test]
+fn test_generic_close() {
+    use terbium::grammar::ast::parse_type;
+    use terbium::grammar::cursor::Cursor;
+    use terbium::grammar::token::lex;
+    use terbium::grammar::Token;
+
+    let ident = |name: &str| TypeExpr::Ident(name.to_string());
+    let generic = |ty, args| TypeExpr::Generic(Box::new(ty), args);
+
+    // The lexer doesn't know about generics, so both `>>` are shifts
+    let tokens = lex("Vec<Vec<Int>> a >> b").unwrap();
+    assert_eq!(tokens[5].0, Token::Operator(Operator::BitRShift));
+    assert_eq!(tokens[7].0, Token::Operator(Operator::BitRShift));
+
+    let mut cursor = Cursor::new(tokens.as_slice());
+    let ty = parse_type(&mut cursor).unwrap();
+    assert_eq!(
+        ty.node(),
+        &generic(
+            ident("Vec"),
+            vec![generic(ident("Vec"), vec![ident("Int")])]
+        ),
+    );
+    assert_eq!(ty.span().range, (0, 13));
+
+    assert_eq!(
+        Expr::parse(cursor.remaining().to_vec()).unwrap(),
+        Expr::BinaryExpr {
+            operator: spanned(Operator::BitRShift),
+            lhs: spanned(Expr::Ident("a".to_string())),
+            rhs: spanned(Expr::Ident("b".to_string())),
+        },
+    );
+
+    let parse = |code: &str| {
+        parse_type(&mut Cursor::new(lex(code).unwrap().as_slice()))
+            .ok()
+            .map(Spanned::into_node)
+    };
+    assert_eq!(
+        parse("Map<a.Key, Vec<Int>>").unwrap(),
+        generic(
+            ident("Map"),
+            vec![
+                TypeExpr::Attr(Box::new(ident("a")), "Key".to_string()),
+                generic(ident("Vec"), vec![ident("Int")]),
+            ],
+        ),
+    );
+    assert_eq!(
+        parse("A<B<C<D>>>").unwrap(),
+        generic(
+            ident("A"),
+            vec![generic(
+                ident("B"),
+                vec![generic(ident("C"), vec![ident("D")])]
+            )],
+        ),
+    );
+    assert_eq!(parse("Int").unwrap(), ident("Int"));
+
+    // Half of the `>>` is left over
+    let tokens = lex("A<B>>").unwrap();
+    let mut cursor = Cursor::new(tokens.as_slice());
+    let error = parse_type(&mut cursor).unwrap_err();
+    assert_eq!(error.span.range, (4, 5));
+    assert_eq!(cursor.position(), 0);
+
+    assert!(parse("Vec<Int").is_none());
+    assert!(parse("Vec<>").is_none());
+}
+
+#[test]
+fn test_parse_import() {
+    use terbium::grammar::ast::{parse_import, ImportItem, ImportStmt};
+    use terbium::grammar::token::lex;
+
+    let import = |code: &str| parse_import(lex(code).unwrap().into_vec());
+    let item = |path: &[&str], alias: Option<&str>| ImportItem {
+        path: path.iter().map(ToString::to_string).collect(),
+        alias: alias.map(ToString::to_string),
+    };
+
+    assert_eq!(
+        import("require std;"),
+        Ok(ImportStmt {
+            from: None,
+            items: vec![item(&["std"], None)],
+        }),
+    );
+    assert_eq!(
+        import("require std.io as io, math"),
+        Ok(ImportStmt {
+            from: None,
+            items: vec![item(&["std", "io"], Some("io")), item(&["math"], None)],
+        }),
+    );
+    assert_eq!(
+        import("from std.io require read, write as put;"),
+        Ok(ImportStmt {
+            from: Some(vec!["std".to_string(), "io".to_string()]),
+            items: vec![item(&["read"], None), item(&["write"], Some("put"))],
+        }),
+    );
+
+    assert!(import("require;").is_err());
+    assert!(import("require").is_err());
+    assert!(import("require std as;").is_err());
+    assert!(import("require std.;").is_err());
+    assert!(import("from std;").is_err());
+}
+
+#[test]
+fn test_parse_let() {
+    use terbium::grammar::ast::{parse_let, BindingModifiers, Target};
+    use terbium::grammar::token::lex;
+    use terbium::grammar::ErrorCode;
+
+    let parse = |code: &str| parse_let(lex(code).unwrap().into_vec());
+    let ident = |name: &str| spanned(Target::Ident(name.to_string()));
+
+    let binding = parse("let mut x = 1;").unwrap();
+    assert_eq!(
+        binding.modifiers,
+        BindingModifiers {
+            private: false,
+            r#const: false,
+            r#mut: true,
+        },
+    );
+    assert_eq!(binding.targets, [ident("x")]);
+    assert_eq!(binding.value, spanned(Expr::Integer(1)));
+
+    let binding = parse("const y = 2").unwrap();
+    assert!(binding.modifiers.r#const && !binding.modifiers.r#mut);
+    assert_eq!(binding.targets, [ident("y")]);
+
+    let binding = parse("private let a = b = 3;").unwrap();
+    assert!(binding.modifiers.private);
+    assert_eq!(binding.targets, [ident("a"), ident("b")]);
+
+    let errors = parse("mut let x = 1").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, ErrorCode::MisorderedModifiers);
+    assert_eq!(errors[0].message, "'let' must come before 'mut'");
+    assert_eq!(errors[0].span.range, (0, 7));
+
+    let errors = parse("let private x = 1").unwrap_err();
+    assert_eq!(errors[0].code, ErrorCode::MisorderedModifiers);
+
+    assert_eq!(
+        parse("const mut x = 1").unwrap_err()[0].code,
+        ErrorCode::ConstMut
+    );
+    assert!(parse("const y").is_err());
+    assert!(parse("x = 1").is_err());
+    assert!(parse("private").is_err());
+    assert!(parse("let x = 1; let y = 2;").is_err());
+}
+
+#[test]
+fn test_ranges() {
+    let int = |i: u128| spanned(Expr::Integer(i));
+    let range = |start, end| Expr::Range { start, end };
+
+    assert_eq!(parse("1..5"), range(Some(int(1)), Some(int(5))));
+    assert_eq!(parse("..5"), range(None, Some(int(5))));
+    assert_eq!(parse("5.."), range(Some(int(5)), None));
+    assert_eq!(parse(".."), range(None, None));
+
+    // Both bounds bind looser than any other operator
+    assert_eq!(
+        parse("a + 1..b | c"),
+        range(
+            Some(spanned(Expr::BinaryExpr {
+                operator: spanned(Operator::Add),
+                lhs: ident("a"),
+                rhs: int(1),
+            })),
+            Some(spanned(Expr::BinaryExpr {
+                operator: spanned(Operator::BitOr),
+                lhs: ident("b"),
+                rhs: ident("c"),
+            })),
+        ),
+    );
+    assert_eq!(
+        parse("f(..)"),
+        Expr::Call {
+            value: ident("f"),
+            args: vec![spanned(range(None, None))],
+            kwargs: vec![],
+        },
+    );
+
+    assert!(Expr::from_string(Source::default(), "1..2..3".to_string()).is_err());
+}
@@ -0,0 +1,48 @@
+use terbium::grammar::registry::{SourceRegistry, SourcedSpan};
+
+#[test]
+fn test_registry_resolution() {
+    let mut registry = SourceRegistry::new();
+    let main = registry.add("src/main.trb", "func main() {\n    lib.run();\n}\n");
+    let lib = registry.add("src/lib.trb", "// héllo wörld ✓\nfunc run() {}\n");
+
+    assert_ne!(main, lib);
+    assert_eq!(registry.name(lib), Some("src/lib.trb"));
+
+    let resolve = |span| registry.resolve(&span).map(|loc| loc.to_string());
+
+    assert_eq!(
+        resolve(SourcedSpan::new(main, 0..4)).unwrap(),
+        "src/main.trb:1:1"
+    );
+    assert_eq!(
+        resolve(SourcedSpan::new(main, 18..21)).unwrap(),
+        "src/main.trb:2:5"
+    );
+    assert_eq!(
+        resolve(SourcedSpan::new(main, 29..30)).unwrap(),
+        "src/main.trb:3:1"
+    );
+
+    // Columns count characters, not bytes
+    assert_eq!(
+        resolve(SourcedSpan::new(lib, 15..16)).unwrap(),
+        "src/lib.trb:1:16"
+    );
+    assert_eq!(
+        resolve(SourcedSpan::new(lib, 22..25)).unwrap(),
+        "src/lib.trb:2:6"
+    );
+
+    let location = registry.resolve(&SourcedSpan::new(lib, 22..25)).unwrap();
+    assert_eq!(
+        (location.file, location.line, location.column),
+        ("src/lib.trb", 2, 6)
+    );
+
+    assert_eq!(resolve(SourcedSpan::new(lib, 1000..1001)), None);
+    assert_eq!(
+        SourceRegistry::new().resolve(&SourcedSpan::new(main, 0..1)),
+        None
+    );
+}
@@ -0,0 +1,94 @@
+use terbium::grammar::sourcemap::{decode_mappings, Mapping, SourceMapBuilder};
+use terbium::grammar::token::lex;
+use terbium::grammar::{Source, Span};
+
+#[test]
+fn test_sourcemap_vlq() {
+    let src = "let x = 1;\nlet y = x;";
+    let mut builder = SourceMapBuilder::new("out.js");
+    builder.add_source(Source::default(), src);
+
+    // Transpiled to `var x = 1;\nvar y = x;`
+    for (line, column, offset) in [(0, 0, 0), (0, 4, 4), (1, 0, 11), (1, 4, 15)] {
+        builder.add_mapping(line, column, &Span::single(Source::default(), offset));
+    }
+    assert_eq!(builder.encode_mappings(), "AAAA,IAAI;AACJ,IAAI");
+
+    // Values of 16 and above need a continuation digit, and empty lines are kept
+    let mut builder = SourceMapBuilder::new("out.js");
+    builder.add_source(Source::default(), src);
+    builder.add_mapping(2, 16, &Span::single(Source::default(), 0));
+    assert_eq!(builder.encode_mappings(), ";;gBAAA");
+
+    assert_eq!(
+        builder.to_json(),
+        r#"{"version":3,"file":"out.js","sources":["<unknown>"],"sourcesContent":["let x = 1;\nlet y = x;"],"names":[],"mappings":";;gBAAA"}"#,
+    );
+}
+
+#[test]
+fn test_sourcemap_round_trip() {
+    let src = "func main() {\n    let value = 1 + 2;\n    print(value);\n}\n";
+    let tokens = lex(src).unwrap();
+    let mut builder = SourceMapBuilder::new("main.js");
+    let other = Source::repl();
+
+    assert_eq!(builder.add_source(Source::default(), src), 0);
+    assert_eq!(builder.add_source(other.clone(), "x"), 1);
+    assert_eq!(builder.add_source(Source::default(), src), 0);
+
+    // Emit every token on its own line, indented by its index
+    for (i, (_, span)) in tokens.iter().enumerate() {
+        builder.add_mapping(i, i % 7, span);
+    }
+    builder.add_mapping(tokens.len(), 0, &Span::single(other, 0));
+
+    let decoded = decode_mappings(&builder.encode_mappings()).unwrap();
+    assert_eq!(decoded, builder.mappings());
+    assert_eq!(
+        decoded[4],
+        Mapping {
+            generated_line: 4,
+            generated_column: 4,
+            source: 0,
+            original_line: 0,
+            original_column: 12,
+        },
+    );
+    assert_eq!(
+        decoded[6],
+        Mapping {
+            generated_line: 6,
+            generated_column: 6,
+            source: 0,
+            original_line: 1,
+            original_column: 8,
+        },
+    );
+    assert_eq!(decoded.last().unwrap().source, 1);
+
+    assert_eq!(decode_mappings("AA!A"), None);
+    assert_eq!(decode_mappings("AA"), None);
+}
+
+#[test]
+fn test_sourcemap_utf16_columns() {
+    // The emoji is one char, but two UTF-16 code units
+    let src = "let a = 1;
+let s = \"\u{1f600}\" + x;";
+    let tokens = lex(src).unwrap();
+    let mut builder = SourceMapBuilder::new("out.js");
+    builder.add_source(Source::default(), src);
+
+    // `x`, 14 chars into the line
+    let (_, x) = &tokens[tokens.len() - 2];
+    assert_eq!(x.start(), 25);
+    builder.add_mapping(0, 0, x);
+
+    assert_eq!(builder.mappings()[0].original_line, 1);
+    assert_eq!(builder.mappings()[0].original_column, 15);
+    assert_eq!(
+        decode_mappings(&builder.encode_mappings()).unwrap(),
+        builder.mappings(),
+    );
+}
@@ -0,0 +1,51 @@
+use terbium::grammar::token::lex_bytes;
+use terbium::grammar::{Source, Span};
+
+#[test]
+fn test_span_offset_conversion() {
+    // 'é' is 2 bytes and 1 UTF-16 unit, '𝕏' is 4 bytes and 2 UTF-16 units
+    let src = "é = \"𝕏y\"; z";
+    let span = |range| Span::from_range(Source::default(), range);
+
+    // The string literal, which starts after a multi-byte char and contains another one
+    let string = span(4..8);
+    assert_eq!(string.to_byte_range(src), 5..12);
+    assert_eq!(string.to_utf16_range(src), 4..9);
+    assert_eq!(&src[string.to_byte_range(src)], "\"𝕏y\"");
+
+    // `z`, after both of them
+    let z = span(10..11);
+    assert_eq!(z.to_byte_range(src), 14..15);
+    assert_eq!(z.to_utf16_range(src), 11..12);
+
+    let ascii = span(0..3);
+    assert_eq!(ascii.to_byte_range("abc"), 0..3);
+    assert_eq!(ascii.to_utf16_range("abc"), 0..3);
+
+    // The end of input span is one past the last char
+    assert_eq!(span(11..12).to_byte_range(src), 15..15);
+    assert_eq!(span(11..12).to_utf16_range(src), 12..12);
+}
+
+#[test]
+fn test_span_len() {
+    let src = "let s = \"héllo\";";
+    let tokens = lex_bytes(src.as_bytes()).unwrap();
+
+    // `é` is one char but two bytes
+    let (string, span) = &tokens[3];
+    assert_eq!(span.len(), 7);
+    assert_eq!(span.byte_len(src), 8);
+    assert_eq!(string.byte_len(), 8);
+
+    for (token, span) in &tokens {
+        assert_eq!(token.byte_len(), span.byte_len(src));
+        assert_eq!(token.byte_len(), token.to_string().len());
+    }
+
+    // A zero-width span at the end of the input
+    let eof = Span::from_range(Source::default(), 16..16);
+    assert_eq!(eof.len(), 0);
+    assert!(eof.is_empty());
+    assert_eq!(eof.byte_len(src), 0);
+}
@@ -0,0 +1,167 @@
+use terbium::grammar::token::{
+    lex, lex_chars, Bracket, Literal, QuoteStyle, StringLiteral, TokenStream,
+};
+use terbium::grammar::{Operator, ParseInterface, Source, Span, Token};
+
+#[test]
+fn test_token_stream_iteration() {
+    let tokens = lex("x = (1 + y)").unwrap();
+
+    assert_eq!(tokens.len(), 7);
+    assert!(!tokens.is_empty());
+    assert!(TokenStream::new().is_empty());
+    assert_eq!(
+        tokens.iter_tokens().cloned().collect::<Vec<_>>(),
+        vec![
+            Token::Identifier("x".to_string()),
+            Token::Assign,
+            Token::StartBracket(Bracket::Paren),
+            Token::Literal(Literal::Integer(1)),
+            Token::Operator(Operator::Add),
+            Token::Identifier("y".to_string()),
+            Token::EndBracket(Bracket::Paren),
+        ],
+    );
+    assert_eq!(
+        tokens.iter_spans().map(Span::start).collect::<Vec<_>>(),
+        vec![0, 2, 4, 5, 7, 9, 10],
+    );
+
+    // Identifiers share a kind regardless of their name
+    let kinds = tokens.kinds().collect::<Vec<_>>();
+    assert_eq!(kinds[0], kinds[5]);
+    assert_ne!(kinds[0], kinds[1]);
+
+    assert_eq!((&tokens).into_iter().count(), 7);
+    let collected = tokens.clone().into_iter().collect::<TokenStream>();
+    assert_eq!(collected, tokens);
+    assert_eq!(
+        TokenStream::from_string(Source::default(), "x = (1 + y)".to_string()).unwrap(),
+        tokens,
+    );
+}
+
+#[test]
+fn test_token_stream_indexing() {
+    let tokens = lex("a + b * c").unwrap();
+
+    assert_eq!(tokens[1].0, Token::Operator(Operator::Add));
+    assert_eq!(tokens[3].1.range(), 6..7);
+    assert_eq!(tokens[1..3], tokens.as_slice()[1..3]);
+
+    let slice = tokens.slice(2..);
+    assert_eq!(slice.len(), 3);
+    assert_eq!(slice[0].0, Token::Identifier("b".to_string()));
+    assert_eq!(tokens.slice(..0), &[]);
+}
+
+#[test]
+fn test_tokens_outlive_source() {
+    use std::sync::OnceLock;
+
+    static CACHE: OnceLock<Vec<Token>> = OnceLock::new();
+
+    fn store<T: 'static>(cell: &'static OnceLock<T>, value: T) -> &'static T {
+        cell.get_or_init(|| value)
+    }
+
+    let src = String::from("let name = \"value\";");
+    let tokens = lex(&src).unwrap();
+    let expected = tokens.iter_tokens().cloned().collect::<Vec<_>>();
+
+    let cached = store(
+        &CACHE,
+        tokens
+            .into_iter()
+            .map(|(token, _)| token.into_owned())
+            .collect(),
+    );
+    drop(src);
+
+    assert_eq!(cached, &expected);
+    assert_eq!(
+        cached[3],
+        Token::Literal(Literal::String(StringLiteral::String(
+            "value".to_string(),
+            QuoteStyle::Double,
+        ))),
+    );
+}
+
+#[test]
+fn test_lex_chars() {
+    assert_eq!(lex_chars("func x".chars()), lex("func x"));
+    assert_eq!(lex_chars("é + \"ü\"".chars()), lex("é + \"ü\""));
+    assert_eq!(lex_chars("".chars()).unwrap().len(), 0);
+
+    let decoded = [0x66_u16, 0x75, 0x6e, 0x63, 0x20, 0x78];
+    let chars = char::decode_utf16(decoded).map(Result::unwrap);
+    assert_eq!(lex_chars(chars), lex("func x"));
+
+    assert_eq!(
+        lex_chars("x $".chars()).unwrap_err(),
+        lex("x $").unwrap_err()
+    );
+
+    // Errors towards the end of the source, whose length isn't known up front
+    for code in ["x = \"abc", "$\"{x", "é \"ü"] {
+        let errors = lex_chars(code.chars()).unwrap_err();
+        assert_eq!(errors, lex(code).unwrap_err());
+        assert!(errors
+            .iter()
+            .all(|e| e.span.range.1 <= code.chars().count() + 1));
+    }
+}
+
+#[test]
+fn test_token_builder() {
+    use terbium::grammar::token::TokenBuilder;
+
+    // Both fragments start at offset 0 of their own source
+    let first = lex("let x = 'a\\n';").unwrap();
+    let second = lex("f(x)").unwrap();
+    assert_eq!(first[0].1.start(), second[0].1.start());
+
+    let mut builder = TokenBuilder::new();
+    builder.append(first.clone());
+    let span = builder.push(Token::Identifier("func".to_string()));
+    builder.append(second.clone());
+
+    assert_eq!(span.src(), Source::generated());
+    assert_eq!(builder.to_source(), "let x = 'a\\n' ; r#func f ( x )");
+    assert_eq!(
+        &builder.to_source()[span.to_byte_range(builder.to_source())],
+        "r#func"
+    );
+
+    let spans = builder
+        .tokens()
+        .iter()
+        .map(|(_, span)| span.range())
+        .collect::<Vec<_>>();
+    assert!(spans.windows(2).all(|w| w[0].end < w[1].start));
+
+    let relexed = lex(builder.to_source()).unwrap();
+    assert_eq!(
+        relexed
+            .iter()
+            .map(|(_, span)| span.range())
+            .collect::<Vec<_>>(),
+        spans,
+    );
+
+    let tokens = builder.finish();
+    assert_eq!(
+        relexed.iter_tokens().collect::<Vec<_>>(),
+        tokens.iter_tokens().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        tokens.iter_tokens().cloned().collect::<Vec<_>>(),
+        first
+            .iter_tokens()
+            .chain([&Token::Identifier("func".to_string())])
+            .chain(second.iter_tokens())
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
+}
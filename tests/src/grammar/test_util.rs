@@ -0,0 +1,254 @@
+use terbium::grammar::token::{Bracket, Keyword, Literal, QuoteStyle, StringLiteral};
+use terbium::grammar::util::{
+    associate_comments, check_brackets, check_indentation, extract_comments, folding_ranges,
+    lex_status, needs_continuation, to_sexp, token_at, tokens_in_range, tree_sitter_kind,
+    CommentKind, LexStatus, RangeMatch,
+};
+use terbium::grammar::{Operator, ParseInterface, Source, Token};
+
+#[test]
+fn test_to_sexp() {
+    let tokens = Vec::from_string(
+        Source::default(),
+        r#"func main() { print("hi", 1 + 2); }"#.to_string(),
+    )
+    .unwrap();
+
+    assert_eq!(
+        to_sexp(&tokens, false),
+        r#"(tokens (kw func) (ident main) (lparen) (rparen) (lbrace) (ident print) (lparen) (string "hi") (comma) (int 1) (op +) (int 2) (rparen) (semicolon) (rbrace))"#,
+    );
+    assert_eq!(
+        to_sexp(&tokens[..2], true),
+        "(tokens (kw func @0..4) (ident main @5..9))",
+    );
+    assert_eq!(to_sexp(&[], true), "(tokens)");
+}
+
+#[test]
+fn test_tree_sitter_kind() {
+    let cases = [
+        (Token::Identifier("x".to_string()), "identifier"),
+        (Token::Keyword(Keyword::Func), "keyword"),
+        (Token::Keyword(Keyword::SelfKw), "self"),
+        (Token::Literal(Literal::Integer(1)), "integer_literal"),
+        (
+            Token::Literal(Literal::Float("1.5".parse().unwrap())),
+            "float_literal",
+        ),
+        (
+            Token::Literal(Literal::String(StringLiteral::String(
+                "a".to_string(),
+                QuoteStyle::Double,
+            ))),
+            "string_literal",
+        ),
+        (Token::Operator(Operator::Add), "binary_operator"),
+        (Token::Operator(Operator::Not), "unary_operator"),
+        (Token::StartBracket(Bracket::Paren), "("),
+        (Token::EndBracket(Bracket::Brace), "}"),
+        (Token::Semicolon, ";"),
+        (Token::Invalid('`'), "ERROR"),
+    ];
+
+    for (token, kind) in cases {
+        assert_eq!(tree_sitter_kind(&token), kind, "{:?}", token);
+    }
+}
+
+#[test]
+fn test_tokens_in_range() {
+    let tokens = Vec::from_string(Source::default(), "let value = 10;".to_string()).unwrap();
+    let find = |range, mode| {
+        tokens_in_range(&tokens, range, mode)
+            .map(|(token, _)| token.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // Covering part of `value`
+    assert_eq!(find(6..8, RangeMatch::Intersecting), vec!["value"]);
+    assert!(find(6..8, RangeMatch::Contained).is_empty());
+    assert_eq!(
+        find(2..11, RangeMatch::Intersecting),
+        vec!["let", "value", "="]
+    );
+    assert_eq!(find(2..11, RangeMatch::Contained), vec!["value", "="]);
+
+    // Between `=` and `10`
+    assert!(find(11..12, RangeMatch::Intersecting).is_empty());
+
+    assert_eq!(find(4..9, RangeMatch::Contained), vec!["value"]);
+    assert_eq!(find(4..9, RangeMatch::Intersecting), vec!["value"]);
+}
+
+#[test]
+fn test_associate_comments() {
+    let src = "// Adds two numbers.
+// Returns their sum.
+func add(a, b) {
+    a + b // the sum
+
+    // Nothing else to do here
+
+}
+";
+    let tokens = Vec::from_string(Source::default(), src.to_string()).unwrap();
+    let groups = associate_comments(src, &tokens);
+
+    assert_eq!(groups.len(), 2);
+
+    let (doc, index) = &groups[0];
+    assert_eq!(tokens[*index].0.to_string(), "func");
+    assert_eq!(
+        doc.comments,
+        ["// Adds two numbers.", "// Returns their sum."]
+    );
+    assert_eq!(doc.span.range(), 0..42);
+    assert!(!doc.trailing);
+
+    let (trailing, index) = &groups[1];
+    assert_eq!(tokens[*index].0.to_string(), "b");
+    assert_eq!(trailing.comments, ["// the sum"]);
+    assert!(trailing.trailing);
+}
+
+#[test]
+fn test_extract_comments() {
+    let src = "let url = \"http://x\"; // TODO: é\r\n/* see // here */ r\"\\\" /* done";
+    let comments = extract_comments(src)
+        .into_iter()
+        .map(|(kind, text, span)| (kind, text, span.range))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        comments,
+        [
+            (CommentKind::Line, " TODO: é".to_string(), (22, 32)),
+            (CommentKind::Block, " see // here ".to_string(), (34, 51)),
+            (CommentKind::Block, " done".to_string(), (57, 64)),
+        ],
+    );
+
+    let (_, _, span) = &extract_comments(src)[0];
+    assert_eq!(&src[span.to_byte_range(src)], "// TODO: é");
+    assert!(extract_comments("'//' \"/*\\\"*/\"").is_empty());
+}
+
+#[test]
+fn test_check_indentation() {
+    assert!(check_indentation("if x {\n\ty\n\tif z {\n\t\tw\n\t}\n}\n").is_ok());
+    assert!(check_indentation("if x {\n    y\n    if z {\n        w\n    }\n}\n").is_ok());
+
+    let errors = check_indentation("if x {\n\ty\n        z\n}").unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message(),
+        "inconsistent use of tabs and spaces in indentation",
+    );
+    assert_eq!(errors[0].span().range(), 10..18);
+}
+
+#[test]
+fn test_token_at() {
+    let tokens = Vec::from_string(Source::default(), "let value=10;".to_string()).unwrap();
+    let at = |offset| token_at(&tokens, offset).map(|i| tokens[i].0.to_string());
+
+    assert_eq!(at(0).as_deref(), Some("let"));
+    assert_eq!(at(6).as_deref(), Some("value"));
+    // Between `let` and `value`
+    assert_eq!(at(3), None);
+    // `value` ends where `=` starts
+    assert_eq!(at(9).as_deref(), Some("="));
+    assert_eq!(at(12).as_deref(), Some(";"));
+    assert_eq!(at(13), None);
+    assert_eq!(at(100), None);
+}
+
+#[test]
+fn test_check_brackets() {
+    let check = |code: &str| {
+        check_brackets(&Vec::from_string(Source::default(), code.to_string()).unwrap()).map_err(
+            |errors| {
+                errors
+                    .iter()
+                    .map(|e| (e.message().to_string(), e.span().range()))
+                    .collect::<Vec<_>>()
+            },
+        )
+    };
+
+    assert_eq!(check("f(a[0], {b: (c)})"), Ok(()));
+    assert_eq!(
+        check("f(x)) + g(y))"),
+        Err(vec![
+            ("unmatched closing parenthesis".to_string(), 4..5),
+            ("unmatched closing parenthesis".to_string(), 12..13),
+        ]),
+    );
+    assert_eq!(
+        check("[a, (b]"),
+        Err(vec![("unclosed parenthesis".to_string(), 4..5)]),
+    );
+    assert_eq!(
+        check("{ } } {"),
+        Err(vec![
+            ("unmatched closing brace".to_string(), 4..5),
+            ("unclosed brace".to_string(), 6..7),
+        ]),
+    );
+}
+
+#[test]
+fn test_folding_ranges() {
+    let src = "func main() {\n    if x { y }\n    /* a\n       b */\n    while z {\n        w\n    }\n}\n/* one line */";
+    let tokens = Vec::from_string(Source::default(), src.to_string()).unwrap();
+    let ranges = |with_comments| {
+        folding_ranges(src, &tokens, with_comments)
+            .iter()
+            .map(|span| span.range())
+            .collect::<Vec<_>>()
+    };
+
+    // The braces of `if x { y }` are on one line, so they are skipped
+    assert_eq!(ranges(false), vec![12..81, 62..79]);
+    assert_eq!(ranges(true), vec![12..81, 33..49, 62..79]);
+
+    // Unbalanced braces are skipped without affecting the others
+    let src = "}\n{\n  a {\n  }\n";
+    let tokens = Vec::from_string(Source::default(), src.to_string()).unwrap();
+    assert_eq!(
+        folding_ranges(src, &tokens, true)
+            .iter()
+            .map(|span| span.range())
+            .collect::<Vec<_>>(),
+        vec![8..13],
+    );
+}
+
+#[test]
+fn test_lex_status() {
+    assert_eq!(lex_status("let x = f(1, [2]);"), LexStatus::Complete);
+    assert_eq!(lex_status(""), LexStatus::Complete);
+    assert_eq!(
+        lex_status("func main() {"),
+        LexStatus::UnclosedBracket(Bracket::Brace)
+    );
+    assert_eq!(
+        lex_status("if x { f(["),
+        LexStatus::UnclosedBracket(Bracket::Bracket)
+    );
+    assert_eq!(lex_status("let s = \"abc"), LexStatus::UnterminatedString);
+    assert_eq!(lex_status("f('a\\'"), LexStatus::UnterminatedString);
+    assert_eq!(lex_status("x /* comment"), LexStatus::UnterminatedComment);
+    assert_eq!(lex_status("x /* comment */ + 1"), LexStatus::Complete);
+
+    assert_eq!(lex_status("f(x))"), LexStatus::Invalid);
+    assert_eq!(lex_status("f(x]"), LexStatus::Invalid);
+    assert_eq!(lex_status("x `"), LexStatus::Invalid);
+
+    assert!(!needs_continuation("1 + 2;"));
+    assert!(needs_continuation("{"));
+    assert!(needs_continuation("\"abc"));
+    assert!(needs_continuation("/*"));
+    assert!(!needs_continuation("}"));
+}
@@ -1,3 +1,4 @@
+#[cfg(feature = "float")]
 pub mod test_float;
 pub mod test_interpreter;
 pub mod test_intrepreter_from_string;
@@ -19,5 +20,5 @@ pub fn interpret(code: &str) -> TerbiumObject {
     let mut interpreter = DefaultInterpreter::default();
     interpreter.run_bytecode(&program);
 
-    interpreter.ctx.pop().clone()
+    *interpreter.ctx.pop()
 }